@@ -5,7 +5,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::primitives::Coalition;
 
-#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct BlockSet {
     blocks: EnumMap<Coalition, i8>,
 }
@@ -51,6 +51,11 @@ impl BlockSet {
     pub fn count(&self, coalition: Coalition) -> i8 {
         self.blocks[coalition]
     }
+
+    /// The number of blocks of each coalition in this set
+    pub fn counts(&self) -> EnumMap<Coalition, i8> {
+        self.blocks
+    }
 }
 
 impl Sum<BlockSet> for BlockSet {
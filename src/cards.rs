@@ -1,15 +1,198 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use thiserror::Error;
+
 pub mod court;
 pub mod event;
 pub mod list;
+pub mod parse;
+
+/// The three kinds of card that make up the deck
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CardKind {
+    Court,
+    Event,
+    Dominance,
+}
+
+/// A stable identifier for a single physical card. Court cards are numbered
+/// 1 through 100 in catalogue order, followed by the 12 event cards and then
+/// the 4 dominance checks. Ids serialize as their number.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "u8", into = "u8")]
+pub struct CardId(u8);
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Error)]
+#[error("no card has id {0}")]
+pub struct InvalidCardId(pub u8);
+
+const FIRST_EVENT: u8 = list::COURT_CARD_COUNT as u8 + 1;
+const FIRST_DOMINANCE: u8 = FIRST_EVENT + list::EVENT_CARD_COUNT as u8;
+const LAST_CARD: u8 = FIRST_DOMINANCE + list::DOMINANCE_CARD_COUNT as u8 - 1;
+
+impl CardId {
+    /// Get the card with this number, if there is one
+    pub fn new(number: u8) -> Option<Self> {
+        (1..=LAST_CARD).contains(&number).then_some(Self(number))
+    }
 
-#[derive(Debug)]
+    /// Iterate over the ids of every card in the game, in order
+    pub fn all() -> impl Iterator<Item = CardId> {
+        (1..=LAST_CARD).map(Self)
+    }
+
+    /// Find a card by name. Names are compared case-insensitively. All four
+    /// dominance checks share a name; looking it up finds the first one.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.trim().to_lowercase();
+        Self::all().find(|id| id.name().to_lowercase() == name)
+    }
+
+    pub fn number(self) -> u8 {
+        self.0
+    }
+
+    pub fn kind(self) -> CardKind {
+        if self.0 < FIRST_EVENT {
+            CardKind::Court
+        } else if self.0 < FIRST_DOMINANCE {
+            CardKind::Event
+        } else {
+            CardKind::Dominance
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self.kind() {
+            CardKind::Court => list::court_card_data(self).name,
+            CardKind::Event => list::event_card_data(self).name,
+            CardKind::Dominance => "Dominance Check",
+        }
+    }
+
+    /// The position of this card within its own kind's list, starting from 0
+    fn index(self) -> usize {
+        (self.0
+            - match self.kind() {
+                CardKind::Court => 1,
+                CardKind::Event => FIRST_EVENT,
+                CardKind::Dominance => FIRST_DOMINANCE,
+            }) as usize
+    }
+}
+
+impl TryFrom<u8> for CardId {
+    type Error = InvalidCardId;
+
+    fn try_from(number: u8) -> Result<Self, Self::Error> {
+        Self::new(number).ok_or(InvalidCardId(number))
+    }
+}
+
+impl From<CardId> for u8 {
+    fn from(id: CardId) -> Self {
+        id.number()
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
 pub enum Card {
     Court(court::Card),
     Event(event::Card),
 }
 
 impl Card {
+    /// Get the card with the given id
+    pub fn from_id(id: CardId) -> Self {
+        match id.kind() {
+            CardKind::Court => Card::Court(court::Card { id }),
+            CardKind::Event => Card::Event(event::Card::Event(event::EventCard { id })),
+            CardKind::Dominance => Card::Event(event::Card::Dominance(id)),
+        }
+    }
+
+    pub fn id(&self) -> CardId {
+        match self {
+            Card::Court(card) => card.id(),
+            Card::Event(card) => card.id(),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.id().name()
+    }
+
     pub fn is_dominance(&self) -> bool {
-        matches!(self, Card::Event(event::Card::Dominance))
+        matches!(self, Card::Event(event::Card::Dominance(_)))
+    }
+}
+
+/// Cards are serialized as their [`CardId`]
+impl Serialize for Card {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.id().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Card {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        CardId::deserialize(deserializer).map(Card::from_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_id_ranges() {
+        let kinds = |kind| CardId::all().filter(|id| id.kind() == kind).count();
+
+        assert_eq!(kinds(CardKind::Court), 100);
+        assert_eq!(kinds(CardKind::Event), 12);
+        assert_eq!(kinds(CardKind::Dominance), 4);
+
+        assert_eq!(CardId::new(0), None);
+        assert_eq!(CardId::new(117), None);
+    }
+
+    #[test]
+    fn test_from_id() {
+        CardId::all().for_each(|id| assert_eq!(Card::from_id(id).id(), id));
+    }
+
+    #[test]
+    fn test_name_lookup() {
+        let money_lenders = CardId::from_name("money LENDERS").unwrap();
+        assert_eq!(money_lenders.kind(), CardKind::Court);
+        assert_eq!(money_lenders.name(), "Money Lenders");
+
+        let rumor = CardId::from_name("Rumor").unwrap();
+        assert_eq!(rumor.kind(), CardKind::Event);
+
+        let dominance = CardId::from_name("dominance check").unwrap();
+        assert_eq!(dominance.kind(), CardKind::Dominance);
+
+        assert_eq!(CardId::from_name("Nobody in Particular"), None);
+
+        // Every card other than the dominance checks has a unique name
+        CardId::all()
+            .filter(|id| id.kind() != CardKind::Dominance)
+            .for_each(|id| assert_eq!(CardId::from_name(id.name()), Some(id)));
+    }
+
+    #[test]
+    fn test_serde_round_trip() {
+        let id = CardId::from_name("Ranjit Singh").unwrap();
+        let json = serde_json::to_string(&id).unwrap();
+
+        assert_eq!(json, id.number().to_string());
+        assert_eq!(serde_json::from_str::<CardId>(&json).unwrap(), id);
+        assert!(serde_json::from_str::<CardId>("200").is_err());
     }
 }
@@ -1,12 +1,14 @@
 use std::ops::Deref;
 
-use serde::{Deserialize, Serialize};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 
 use crate::{
     map::Region,
     primitives::{Coalition, Suit},
 };
 
+use super::{list, CardId, CardKind};
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum Rank {
     One = 1,
@@ -20,7 +22,7 @@ impl Rank {
     }
 }
 
-#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Impact {
     pub armies: i8,
     pub roads: i8,
@@ -30,7 +32,7 @@ pub struct Impact {
     pub favor: Option<Suit>,
 }
 
-#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ActionSet {
     pub tax: bool,
     pub gift: bool,
@@ -67,9 +69,12 @@ impl ActionSet {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct CardData {
-    pub name: &'static str,
+/// The printed details of a single court card. The name is borrowed so that
+/// card data can be parsed at runtime (see [`super::parse`]); the built-in
+/// cards all use `CardData<'static>`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CardData<'a> {
+    pub name: &'a str,
     pub rank: Rank,
     pub suit: Suit,
     pub region: Region,
@@ -147,21 +152,55 @@ pub enum SpecialAbility {
     WellConnected,
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
 pub struct Card {
-    pub(super) data: &'static CardData,
+    pub(super) id: CardId,
+}
+
+impl Card {
+    /// Get the court card with the given id, if it is a court card
+    pub fn from_id(id: CardId) -> Option<Self> {
+        (id.kind() == CardKind::Court).then_some(Self { id })
+    }
+
+    pub fn id(&self) -> CardId {
+        self.id
+    }
+}
+
+/// Court cards are serialized as their [`CardId`]
+impl Serialize for Card {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.id.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Card {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let id = CardId::deserialize(deserializer)?;
+
+        Self::from_id(id).ok_or_else(|| {
+            de::Error::custom(format_args!("card {} is not a court card", id.number()))
+        })
+    }
 }
 
 impl Deref for Card {
-    type Target = CardData;
+    type Target = CardData<'static>;
 
     fn deref(&self) -> &Self::Target {
-        self.data
+        list::court_card_data(self.id)
     }
 }
 
-impl AsRef<CardData> for Card {
-    fn as_ref(&self) -> &CardData {
-        self.data
+impl AsRef<CardData<'static>> for Card {
+    fn as_ref(&self) -> &CardData<'static> {
+        self
     }
 }
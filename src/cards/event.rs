@@ -1,5 +1,11 @@
+use std::ops::Deref;
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
 use crate::{map::Region, primitives::Suit};
 
+use super::{list, CardId, CardKind};
+
 #[derive(Debug, Clone, Copy)]
 pub enum DiscardEvent {
     /// The current climate changes to this
@@ -63,14 +69,87 @@ pub enum PurchaseEvent {
     PersianAristocracy,
 }
 
+impl PurchaseEvent {
+    /// Check if the buyer has to make a decision when this event is
+    /// purchased
+    pub fn needs_choice(self) -> bool {
+        matches!(
+            self,
+            PurchaseEvent::Rumor
+                | PurchaseEvent::OtherPersuasiveMethods
+                | PurchaseEvent::PashtunwaliValues
+                | PurchaseEvent::Rebuke
+        )
+    }
+}
+
+/// The printed details of an event card. Event cards are named for their
+/// purchase effect.
 #[derive(Debug)]
-pub struct EventCard {
+pub struct EventCardData {
+    pub name: &'static str,
     pub discard: DiscardEvent,
     pub purchase: PurchaseEvent,
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
+pub struct EventCard {
+    pub(super) id: CardId,
+}
+
+impl EventCard {
+    pub fn id(&self) -> CardId {
+        self.id
+    }
+}
+
+impl Deref for EventCard {
+    type Target = EventCardData;
+
+    fn deref(&self) -> &Self::Target {
+        list::event_card_data(self.id)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
 pub enum Card {
     Event(EventCard),
-    Dominance,
+    Dominance(CardId),
+}
+
+impl Card {
+    pub fn id(&self) -> CardId {
+        match *self {
+            Card::Event(ref card) => card.id(),
+            Card::Dominance(id) => id,
+        }
+    }
+}
+
+/// Event and dominance cards are serialized as their [`CardId`]
+impl Serialize for Card {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.id().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Card {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let id = CardId::deserialize(deserializer)?;
+
+        match id.kind() {
+            CardKind::Event => Ok(Card::Event(EventCard { id })),
+            CardKind::Dominance => Ok(Card::Dominance(id)),
+            CardKind::Court => Err(de::Error::custom(format_args!(
+                "card {} is not an event card",
+                id.number()
+            ))),
+        }
+    }
 }
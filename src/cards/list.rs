@@ -1,30 +1,718 @@
-use crate::{map::Region, primitives::Suit};
+use crate::{
+    map::Region::{self, *},
+    primitives::{
+        Coalition::{self, *},
+        Suit::{self, *},
+    },
+};
 
-use super::{court, event};
+use super::{
+    court::{
+        self, ActionSet,
+        CardAction::{self, *},
+        CardData, Impact,
+        Rank::{self, *},
+        SpecialAbility::{self, *},
+    },
+    event::{self, EventCardData},
+    CardId, CardKind,
+};
 
-static COURT_CARDS: [court::CardData; 0] = [];
+pub(super) const COURT_CARD_COUNT: usize = 100;
+pub(super) const EVENT_CARD_COUNT: usize = 12;
+pub(super) const DOMINANCE_CARD_COUNT: usize = 4;
 
-pub fn all_court_cards() -> impl Iterator<Item = court::Card> {
-    COURT_CARDS.iter().map(|card| court::Card { data: card })
+/// Helper for building a court card with no impact icons, actions, or
+/// abilities. The rest of the card is filled in with the builder methods
+/// below.
+const fn court(name: &'static str, suit: Suit, rank: Rank, region: Region) -> CardData<'static> {
+    CardData {
+        name,
+        rank,
+        suit,
+        region,
+        patriot: None,
+        prize: None,
+        impact: Impact {
+            armies: 0,
+            roads: 0,
+            spies: 0,
+            tribes: 0,
+            leverage: false,
+            favor: None,
+        },
+        actions: ActionSet {
+            tax: false,
+            gift: false,
+            build: false,
+            movement: false,
+            betray: false,
+            battle: false,
+        },
+        ability: None,
+    }
 }
 
-pub fn all_event_cards() -> impl Iterator<Item = event::EventCard> {
+impl CardData<'static> {
+    const fn patriot(mut self, coalition: Coalition) -> Self {
+        self.patriot = Some(coalition);
+        self
+    }
+
+    const fn prize(mut self, coalition: Coalition) -> Self {
+        self.prize = Some(coalition);
+        self
+    }
+
+    const fn armies(mut self, count: i8) -> Self {
+        self.impact.armies = count;
+        self
+    }
+
+    const fn roads(mut self, count: i8) -> Self {
+        self.impact.roads = count;
+        self
+    }
+
+    const fn spies(mut self, count: i8) -> Self {
+        self.impact.spies = count;
+        self
+    }
+
+    const fn tribes(mut self, count: i8) -> Self {
+        self.impact.tribes = count;
+        self
+    }
+
+    const fn leverage(mut self) -> Self {
+        self.impact.leverage = true;
+        self
+    }
+
+    const fn favor(mut self, suit: Suit) -> Self {
+        self.impact.favor = Some(suit);
+        self
+    }
+
+    const fn actions<const N: usize>(mut self, actions: [CardAction; N]) -> Self {
+        let mut i = 0;
+
+        while i < N {
+            match actions[i] {
+                Tax => self.actions.tax = true,
+                Gift => self.actions.gift = true,
+                Build => self.actions.build = true,
+                Move => self.actions.movement = true,
+                Betray => self.actions.betray = true,
+                Battle => self.actions.battle = true,
+            }
+            i += 1;
+        }
+
+        self
+    }
+
+    const fn ability(mut self, ability: SpecialAbility) -> Self {
+        self.ability = Some(ability);
+        self
+    }
+}
+
+/// All of the court cards in the base game, grouped by region
+static COURT_CARDS: [CardData<'static>; COURT_CARD_COUNT] = [
+    // Kabul
+    court("Mohan Lal", Intelligence, One, Kabul)
+        .patriot(Britain)
+        .spies(1)
+        .actions([Move, Betray])
+        .ability(CharismaticCourtiers),
+    court("Jan-Fishan Khan", Military, Two, Kabul)
+        .patriot(Afghanistan)
+        .armies(1)
+        .tribes(1)
+        .actions([Battle, Move]),
+    court("Prince Akbar Khan", Military, Three, Kabul)
+        .patriot(Afghanistan)
+        .armies(2)
+        .tribes(1)
+        .actions([Battle, Build])
+        .ability(Insurrection),
+    court("Dost Mohammad", Political, Three, Kabul)
+        .patriot(Afghanistan)
+        .tribes(2)
+        .favor(Political)
+        .actions([Tax, Gift, Build]),
+    court("Shah Shujah Durrani", Political, Two, Kabul)
+        .patriot(Britain)
+        .tribes(1)
+        .actions([Tax, Gift])
+        .ability(ClaimOfAncientLineage),
+    court("Aminullah Khan Logari", Political, Two, Kabul)
+        .patriot(Afghanistan)
+        .armies(1)
+        .tribes(1)
+        .actions([Gift, Battle])
+        .ability(Insurrection),
+    court("Mir Masjidi", Military, One, Kabul)
+        .patriot(Afghanistan)
+        .tribes(1)
+        .actions([Battle]),
+    court("Alexander Burnes", Intelligence, Three, Kabul)
+        .patriot(Britain)
+        .spies(2)
+        .actions([Tax, Move, Betray]),
+    court("William Hay Macnaghten", Political, Two, Kabul)
+        .patriot(Britain)
+        .armies(1)
+        .roads(1)
+        .actions([Gift, Build]),
+    court("Charles Masson", Intelligence, Two, Kabul)
+        .prize(Britain)
+        .spies(1)
+        .actions([Tax, Move]),
+    court("Bala Hissar", Military, Two, Kabul)
+        .armies(2)
+        .actions([Build, Battle])
+        .ability(Citadel),
+    court("Kabul Bazaar", Economic, One, Kabul)
+        .roads(1)
+        .actions([Tax, Gift]),
+    court("Money Lenders", Economic, Two, Kabul)
+        .roads(2)
+        .leverage()
+        .actions([Gift, Build]),
+    court("Afghan Handicrafts", Economic, One, Kabul)
+        .leverage()
+        .actions([Tax]),
+    court("Balkh Arsenic Mine", Economic, Two, Kabul)
+        .prize(Russia)
+        .roads(1)
+        .actions([Tax, Build]),
+    court("Murad Beg", Political, One, Kabul)
+        .prize(Russia)
+        .tribes(1)
+        .actions([Tax, Battle]),
+    court("Haji Khan Kakar", Intelligence, One, Kabul)
+        .spies(1)
+        .tribes(1)
+        .actions([Betray]),
+    // Punjab
+    court("Ranjit Singh", Political, Three, Punjab)
+        .armies(1)
+        .tribes(2)
+        .favor(Political)
+        .actions([Tax, Gift, Build]),
+    court("Hari Singh Nalwa", Military, Three, Punjab)
+        .armies(2)
+        .actions([Move, Battle]),
+    court("Paolo Avitabile", Political, Two, Punjab)
+        .tribes(1)
+        .actions([Tax, Build]),
+    court("Jean-Francois Allard", Military, Two, Punjab)
+        .prize(Britain)
+        .armies(1)
+        .actions([Build, Battle]),
+    court("Josiah Harlan", Intelligence, Two, Punjab)
+        .spies(1)
+        .actions([Move, Betray])
+        .ability(SavvyOperator),
+    court("Claude Wade", Intelligence, Three, Punjab)
+        .patriot(Britain)
+        .spies(2)
+        .actions([Tax, Move, Betray])
+        .ability(IndispensableAdvisors),
+    court("Sikh Merchants in Lahore", Economic, Two, Punjab)
+        .roads(1)
+        .leverage()
+        .actions([Tax, Gift])
+        .ability(RegionInfluence),
+    court("Company Commissariat", Economic, Two, Punjab)
+        .patriot(Britain)
+        .armies(1)
+        .roads(1)
+        .actions([Build, Move])
+        .ability(IndianSupplies),
+    court("Lord Auckland", Political, Three, Punjab)
+        .patriot(Britain)
+        .armies(1)
+        .roads(1)
+        .favor(Military)
+        .actions([Tax, Gift, Build])
+        .ability(CoalitionInfluence),
+    court("John Keane", Military, Two, Punjab)
+        .patriot(Britain)
+        .armies(2)
+        .actions([Move, Battle]),
+    court("Khalsa Army", Military, One, Punjab)
+        .armies(1)
+        .actions([Battle]),
+    court("Zorawar Singh Kahluria", Military, One, Punjab)
+        .armies(1)
+        .tribes(1)
+        .actions([Move, Battle]),
+    court("Maqpon Dynasty", Political, One, Punjab)
+        .tribes(1)
+        .actions([Gift]),
+    court("Nanakshahi Mint", Economic, One, Punjab)
+        .leverage()
+        .actions([Tax]),
+    court("Indus Flotilla", Economic, One, Punjab)
+        .roads(1)
+        .actions([Move]),
+    court("Henry Fane", Military, One, Punjab)
+        .armies(1)
+        .actions([Build]),
+    court("Shikarpur Bankers", Economic, Three, Punjab)
+        .roads(2)
+        .leverage()
+        .favor(Economic)
+        .actions([Tax, Gift, Build])
+        .ability(Infrastructure),
+    // Kandahar
+    court("Kohandil Khan", Political, Three, Kandahar)
+        .patriot(Afghanistan)
+        .tribes(2)
+        .actions([Tax, Gift, Battle])
+        .ability(CoalitionInfluence),
+    court("Rahmdil Khan", Political, Two, Kandahar)
+        .patriot(Afghanistan)
+        .tribes(1)
+        .actions([Tax, Build]),
+    court("Mehrdil Khan", Political, One, Kandahar)
+        .tribes(1)
+        .actions([Gift]),
+    court("William Nott", Military, Three, Kandahar)
+        .patriot(Britain)
+        .armies(2)
+        .roads(1)
+        .actions([Build, Move, Battle]),
+    court("Bolan Pass", Economic, Two, Kandahar)
+        .roads(2)
+        .actions([Build, Move])
+        .ability(Infrastructure),
+    court("Kandahar Caravanserai", Economic, One, Kandahar)
+        .prize(Afghanistan)
+        .roads(1)
+        .actions([Tax]),
+    court("Durrani Horsemen", Military, Two, Kandahar)
+        .patriot(Afghanistan)
+        .armies(1)
+        .tribes(1)
+        .actions([Move, Battle])
+        .ability(Irregulars),
+    court("Henry Rawlinson", Intelligence, Two, Kandahar)
+        .patriot(Britain)
+        .spies(1)
+        .actions([Tax, Move])
+        .ability(Blackmail),
+    court("Harry Flashman", Intelligence, One, Kandahar)
+        .patriot(Britain)
+        .spies(1)
+        .actions([Betray])
+        .ability(SavvyOperator),
+    court("Pashtun Mercenaries", Military, One, Kandahar)
+        .armies(1)
+        .actions([Battle]),
+    court("Khojak Pass", Economic, One, Kandahar)
+        .roads(1)
+        .actions([Build]),
+    court("Ghilzai Tribes", Political, Two, Kandahar)
+        .patriot(Afghanistan)
+        .tribes(2)
+        .actions([Tax, Battle]),
+    court("Sindh Grain Traders", Economic, Two, Kandahar)
+        .prize(Britain)
+        .roads(1)
+        .leverage()
+        .actions([Tax, Gift]),
+    court("Mullah Shakur", Intelligence, One, Kandahar)
+        .patriot(Afghanistan)
+        .spies(1)
+        .tribes(1)
+        .actions([Move, Betray]),
+    court("Shrine of the Cloak", Political, One, Kandahar)
+        .tribes(1)
+        .favor(Political)
+        .actions([Gift]),
+    court("Akhtar Khan Alizai", Military, Two, Kandahar)
+        .patriot(Afghanistan)
+        .armies(2)
+        .actions([Battle]),
+    // Herat
+    court("Kamran Shah", Political, Three, Herat)
+        .patriot(Afghanistan)
+        .tribes(2)
+        .favor(Political)
+        .actions([Tax, Gift, Build])
+        .ability(Bodyguards),
+    court("Yar Mohammad Alikozai", Political, Three, Herat)
+        .armies(1)
+        .tribes(1)
+        .actions([Tax, Betray, Battle])
+        .ability(CivilServiceReforms),
+    court("Eldred Pottinger", Military, Two, Herat)
+        .patriot(Britain)
+        .armies(1)
+        .actions([Build, Battle]),
+    court("D'Arcy Todd", Intelligence, Two, Herat)
+        .patriot(Britain)
+        .spies(1)
+        .actions([Gift, Move]),
+    court("Herat Bazaar", Economic, One, Herat)
+        .roads(1)
+        .actions([Tax]),
+    court("Herati Carpet Weavers", Economic, Two, Herat)
+        .roads(1)
+        .leverage()
+        .actions([Tax, Gift])
+        .ability(RegionInfluence),
+    court("Siege of Herat", Military, Three, Herat)
+        .patriot(Russia)
+        .armies(3)
+        .actions([Build, Battle]),
+    court("Ivan Blaramberg", Intelligence, Two, Herat)
+        .patriot(Russia)
+        .spies(2)
+        .actions([Move, Betray]),
+    court("Herati Ghazis", Military, One, Herat)
+        .patriot(Afghanistan)
+        .armies(1)
+        .tribes(1)
+        .actions([Battle]),
+    court("Hazara Chiefs", Political, One, Herat)
+        .prize(Afghanistan)
+        .tribes(1)
+        .actions([Tax]),
+    court("Aimaq Tribesmen", Military, One, Herat)
+        .tribes(1)
+        .actions([Move, Battle]),
+    court("Herat Horse Traders", Economic, One, Herat)
+        .roads(1)
+        .actions([Move]),
+    court("Musalla Complex", Political, Two, Herat)
+        .tribes(1)
+        .favor(Political)
+        .actions([Gift, Build]),
+    court("Herat Guild of Couriers", Intelligence, One, Herat)
+        .spies(1)
+        .actions([Move])
+        .ability(SafeHouse),
+    court("Sher Muhammad Khan", Political, Two, Herat)
+        .patriot(Afghanistan)
+        .tribes(1)
+        .actions([Gift, Battle]),
+    court("Herat Customs House", Economic, Two, Herat)
+        .prize(Afghanistan)
+        .leverage()
+        .actions([Tax, Build]),
+    court("Mir Alam Khan", Intelligence, One, Herat)
+        .spies(1)
+        .actions([Betray])
+        .ability(Blackmail),
+    // Persia
+    court("Mohammad Shah Qajar", Political, Three, Persia)
+        .patriot(Russia)
+        .armies(1)
+        .tribes(1)
+        .favor(Military)
+        .actions([Gift, Build, Battle]),
+    court("Hajji Mirza Aghasi", Political, Two, Persia)
+        .patriot(Russia)
+        .tribes(1)
+        .actions([Tax, Gift])
+        .ability(CharismaticCourtiers),
+    court("Ivan Simonich", Intelligence, Three, Persia)
+        .patriot(Russia)
+        .spies(2)
+        .actions([Gift, Move, Betray])
+        .ability(CoalitionInfluence),
+    court("John McNeill", Intelligence, Two, Persia)
+        .patriot(Britain)
+        .spies(1)
+        .actions([Gift, Move]),
+    court("Persian Army", Military, Three, Persia)
+        .armies(2)
+        .actions([Build, Move, Battle]),
+    court("Nizam Regiment", Military, Two, Persia)
+        .patriot(Russia)
+        .armies(1)
+        .actions([Build, Battle]),
+    court("Qajar Bodyguard", Military, One, Persia)
+        .armies(1)
+        .actions([Battle]),
+    court("Mashhad Shrine", Political, One, Persia)
+        .tribes(1)
+        .actions([Gift]),
+    court("Tabriz Merchants", Economic, Two, Persia)
+        .roads(1)
+        .leverage()
+        .actions([Tax, Gift]),
+    court("Kerman Carpet Weavers", Economic, One, Persia)
+        .roads(1)
+        .actions([Tax]),
+    court("Bandar Abbas Traders", Economic, One, Persia)
+        .prize(Britain)
+        .leverage()
+        .actions([Tax]),
+    court("Justin Sheil", Intelligence, One, Persia)
+        .patriot(Britain)
+        .spies(1)
+        .actions([Betray]),
+    court("Henry Ellis", Political, One, Persia)
+        .patriot(Britain)
+        .tribes(1)
+        .actions([Gift]),
+    court("Asaf al-Dowleh", Political, Two, Persia)
+        .tribes(2)
+        .actions([Tax, Build])
+        .ability(RegionInfluence),
+    court("Khorasan Nomads", Military, One, Persia)
+        .armies(1)
+        .tribes(1)
+        .actions([Move, Battle]),
+    court("Isfahan Arsenal", Economic, Two, Persia)
+        .armies(1)
+        .roads(1)
+        .favor(Economic)
+        .actions([Build]),
+    // Transcaspia
+    court("Nasrullah Khan", Political, Three, Transcaspia)
+        .tribes(2)
+        .actions([Tax, Betray, Battle]),
+    court("Ark of Bukhara", Military, Two, Transcaspia)
+        .armies(2)
+        .actions([Build, Battle])
+        .ability(Citadel),
+    court("Charles Stoddart", Intelligence, One, Transcaspia)
+        .patriot(Britain)
+        .spies(1)
+        .actions([Move]),
+    court("Arthur Conolly", Intelligence, Two, Transcaspia)
+        .patriot(Britain)
+        .spies(2)
+        .actions([Move, Betray])
+        .ability(WellConnected),
+    court("Jan Prosper Witkiewicz", Intelligence, Three, Transcaspia)
+        .patriot(Russia)
+        .spies(2)
+        .actions([Tax, Move, Betray])
+        .ability(StrangeBedfellows),
+    court("Nikolai Muraviev", Intelligence, Two, Transcaspia)
+        .patriot(Russia)
+        .spies(1)
+        .actions([Gift, Move]),
+    court("Vasily Perovsky", Military, Three, Transcaspia)
+        .patriot(Russia)
+        .armies(2)
+        .roads(1)
+        .actions([Build, Move, Battle]),
+    court("Khivan Slave Market", Economic, Two, Transcaspia)
+        .roads(1)
+        .leverage()
+        .actions([Tax, Gift]),
+    court("Bukharan Merchants", Economic, One, Transcaspia)
+        .roads(1)
+        .actions([Tax]),
+    court("Orenburg Cossacks", Military, Two, Transcaspia)
+        .patriot(Russia)
+        .armies(2)
+        .actions([Move, Battle])
+        .ability(Irregulars),
+    court("Turkmen Raiders", Military, One, Transcaspia)
+        .armies(1)
+        .tribes(1)
+        .actions([Battle]),
+    court("Allah Quli Khan", Political, Two, Transcaspia)
+        .patriot(Russia)
+        .tribes(1)
+        .actions([Tax, Build])
+        .ability(Bodyguards),
+    court("Joseph Wolff", Intelligence, One, Transcaspia)
+        .spies(1)
+        .actions([Betray])
+        .ability(SafeHouse),
+    court("Orenburg Trading Company", Economic, Three, Transcaspia)
+        .patriot(Russia)
+        .roads(2)
+        .leverage()
+        .favor(Economic)
+        .actions([Tax, Gift, Build]),
+    court("Aral Sea Flotilla", Economic, One, Transcaspia)
+        .prize(Russia)
+        .roads(1)
+        .actions([Move]),
+    court("Kush Begi", Political, One, Transcaspia)
+        .patriot(Russia)
+        .spies(1)
+        .tribes(1)
+        .actions([Gift]),
+    court("Samarkand Caravans", Economic, Two, Transcaspia)
+        .roads(2)
+        .actions([Tax, Move]),
+];
+
+static EVENT_CARDS: [EventCardData; EVENT_CARD_COUNT] = {
     use event::{DiscardEvent::*, PurchaseEvent::*};
 
+    const fn event(
+        name: &'static str,
+        discard: event::DiscardEvent,
+        purchase: event::PurchaseEvent,
+    ) -> EventCardData {
+        EventCardData {
+            name,
+            discard,
+            purchase,
+        }
+    }
+
     [
-        (NoEffect, PublicWithdrawal),
-        (ConfidenceFailure, OtherPersuasiveMethods),
-        (ChangeSuit(Suit::Political), Rebuke),
-        (Riots(Region::Persia), PersianAristocracy),
-        (DisregardForCustoms, CourtlyManners),
-        (EmbarrassmentOfRiches, KohINoorRecovered),
-        (FailureToImpress, Rumor),
-        (ChangeSuit(Suit::Intelligence), PashtunwaliValues),
-        (Riots(Region::Herat), Nationalism),
-        (Riots(Region::Punjab), ConflictFatigue),
-        (Riots(Region::Kabul), NationBuilding),
-        (ChangeSuit(Suit::Military), NewTactics),
+        event("Public Withdrawal", NoEffect, PublicWithdrawal),
+        event(
+            "Other Persuasive Methods",
+            ConfidenceFailure,
+            OtherPersuasiveMethods,
+        ),
+        event("Rebuke", ChangeSuit(Suit::Political), Rebuke),
+        event(
+            "Persian Aristocracy",
+            Riots(Region::Persia),
+            PersianAristocracy,
+        ),
+        event("Courtly Manners", DisregardForCustoms, CourtlyManners),
+        event(
+            "Koh-i-Noor Recovered",
+            EmbarrassmentOfRiches,
+            KohINoorRecovered,
+        ),
+        event("Rumor", FailureToImpress, Rumor),
+        event(
+            "Pashtunwali Values",
+            ChangeSuit(Suit::Intelligence),
+            PashtunwaliValues,
+        ),
+        event("Nationalism", Riots(Region::Herat), Nationalism),
+        event("Conflict Fatigue", Riots(Region::Punjab), ConflictFatigue),
+        event("Nation Building", Riots(Region::Kabul), NationBuilding),
+        event("New Tactics", ChangeSuit(Suit::Military), NewTactics),
     ]
-    .into_iter()
-    .map(|(discard, purchase)| event::EventCard { purchase, discard })
+};
+
+pub(super) fn court_card_data(id: CardId) -> &'static CardData<'static> {
+    debug_assert_eq!(id.kind(), CardKind::Court);
+    &COURT_CARDS[id.index()]
+}
+
+pub(super) fn event_card_data(id: CardId) -> &'static EventCardData {
+    debug_assert_eq!(id.kind(), CardKind::Event);
+    &EVENT_CARDS[id.index()]
+}
+
+fn ids_of_kind(kind: CardKind) -> impl Iterator<Item = CardId> {
+    CardId::all().filter(move |id| id.kind() == kind)
+}
+
+pub fn all_court_cards() -> impl Iterator<Item = court::Card> {
+    ids_of_kind(CardKind::Court).map(|id| court::Card { id })
+}
+
+pub fn all_event_cards() -> impl Iterator<Item = event::EventCard> {
+    ids_of_kind(CardKind::Event).map(|id| event::EventCard { id })
+}
+
+pub fn all_dominance_cards() -> impl Iterator<Item = event::Card> {
+    ids_of_kind(CardKind::Dominance).map(event::Card::Dominance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::HashSet;
+
+    use enum_map::{enum_map, EnumMap};
+
+    fn count_by<K: enum_map::Enum<usize>>(
+        key: impl Fn(&CardData) -> Option<K>,
+    ) -> EnumMap<K, usize> {
+        COURT_CARDS
+            .iter()
+            .filter_map(key)
+            .fold(EnumMap::default(), |mut counts, key| {
+                counts[key] += 1;
+                counts
+            })
+    }
+
+    #[test]
+    fn test_card_count() {
+        assert_eq!(all_court_cards().count(), 100);
+        assert_eq!(all_event_cards().count(), 12);
+        assert_eq!(all_dominance_cards().count(), 4);
+    }
+
+    #[test]
+    fn test_unique_names() {
+        let names: HashSet<&str> = COURT_CARDS.iter().map(|card| card.name).collect();
+        assert_eq!(names.len(), COURT_CARDS.len());
+    }
+
+    #[test]
+    fn test_suit_counts() {
+        assert_eq!(
+            count_by(|card| Some(card.suit)),
+            enum_map! {
+                Political => 27,
+                Intelligence => 21,
+                Economic => 26,
+                Military => 26,
+            }
+        );
+    }
+
+    #[test]
+    fn test_region_counts() {
+        assert_eq!(
+            count_by(|card| Some(card.region)),
+            enum_map! {
+                Transcaspia => 17,
+                Persia => 16,
+                Herat => 17,
+                Kabul => 17,
+                Kandahar => 16,
+                Punjab => 17,
+            }
+        );
+    }
+
+    #[test]
+    fn test_patriot_counts() {
+        assert_eq!(
+            count_by(|card| card.patriot),
+            enum_map! {
+                Britain => 18,
+                Russia => 13,
+                Afghanistan => 14,
+            }
+        );
+    }
+
+    #[test]
+    fn test_prize_counts() {
+        assert_eq!(
+            count_by(|card| card.prize),
+            enum_map! {
+                Britain => 4,
+                Russia => 3,
+                Afghanistan => 3,
+            }
+        );
+    }
+
+    #[test]
+    fn test_every_card_has_an_action() {
+        COURT_CARDS
+            .iter()
+            .for_each(|card| assert!(card.actions.count() > 0, "{} has no actions", card.name));
+    }
 }
@@ -0,0 +1,662 @@
+use std::collections::HashMap;
+
+use thiserror::Error;
+
+use crate::{
+    map::Region,
+    primitives::{Coalition, Suit},
+};
+
+use super::court::{ActionSet, CardAction, CardData, Impact, Rank, SpecialAbility};
+
+/// The specific problem encountered while parsing a card list
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ParseErrorKind {
+    #[error("missing ':' after the card name")]
+    MissingColon,
+
+    #[error("missing card name")]
+    MissingName,
+
+    #[error("expected {expected}, found end of line")]
+    UnexpectedEnd { expected: &'static str },
+
+    #[error("expected {expected}, found {found:?}")]
+    Expected {
+        expected: &'static str,
+        found: String,
+    },
+
+    #[error("unknown keyword {0:?}")]
+    UnknownKeyword(String),
+
+    #[error("malformed impact: {0}")]
+    MalformedImpact(&'static str),
+
+    #[error("duplicate action {0:?}")]
+    DuplicateAction(CardAction),
+
+    #[error("duplicate card name {name:?} (first defined on line {first_line})")]
+    DuplicateName { name: String, first_line: usize },
+}
+
+/// An error parsing a card list. Lines and columns are both 1-indexed, and
+/// columns are counted in characters.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("line {line}, column {column}: {kind}")]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub kind: ParseErrorKind,
+}
+
+/// A single word (or `,` / `;` separator) from a line, along with its column
+#[derive(Debug, Clone, Copy)]
+struct Token<'a> {
+    column: usize,
+    text: &'a str,
+}
+
+impl Token<'_> {
+    fn is(&self, keyword: &str) -> bool {
+        self.text.eq_ignore_ascii_case(keyword)
+    }
+}
+
+/// Split some text into tokens. `offset` is the character column of the start
+/// of `text`, minus one.
+fn tokenize(text: &str, offset: usize) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut start: Option<(usize, usize)> = None;
+
+    for (column, (index, c)) in text.char_indices().enumerate() {
+        let column = column + offset + 1;
+
+        if c.is_whitespace() || c == ',' || c == ';' {
+            if let Some((start_column, start_index)) = start.take() {
+                tokens.push(Token {
+                    column: start_column,
+                    text: &text[start_index..index],
+                });
+            }
+
+            if !c.is_whitespace() {
+                tokens.push(Token {
+                    column,
+                    text: &text[index..index + c.len_utf8()],
+                });
+            }
+        } else if start.is_none() {
+            start = Some((column, index));
+        }
+    }
+
+    if let Some((column, index)) = start {
+        tokens.push(Token {
+            column,
+            text: &text[index..],
+        });
+    }
+
+    tokens
+}
+
+fn parse_suit(word: &str) -> Option<Suit> {
+    match word.to_ascii_lowercase().as_str() {
+        "political" => Some(Suit::Political),
+        "intelligence" => Some(Suit::Intelligence),
+        "economic" => Some(Suit::Economic),
+        "military" => Some(Suit::Military),
+        _ => None,
+    }
+}
+
+fn parse_rank(word: &str) -> Option<Rank> {
+    match word {
+        "1" => Some(Rank::One),
+        "2" => Some(Rank::Two),
+        "3" => Some(Rank::Three),
+        _ => None,
+    }
+}
+
+fn parse_region(word: &str) -> Option<Region> {
+    match word.to_ascii_lowercase().as_str() {
+        "transcaspia" => Some(Region::Transcaspia),
+        "persia" => Some(Region::Persia),
+        "herat" => Some(Region::Herat),
+        "kabul" => Some(Region::Kabul),
+        "kandahar" => Some(Region::Kandahar),
+        "punjab" => Some(Region::Punjab),
+        _ => None,
+    }
+}
+
+fn parse_coalition(word: &str) -> Option<Coalition> {
+    match word.to_ascii_lowercase().as_str() {
+        "british" => Some(Coalition::Britain),
+        "russian" => Some(Coalition::Russia),
+        "afghan" => Some(Coalition::Afghanistan),
+        _ => None,
+    }
+}
+
+fn parse_action(word: &str) -> Option<CardAction> {
+    match word.to_ascii_lowercase().as_str() {
+        "tax" => Some(CardAction::Tax),
+        "gift" => Some(CardAction::Gift),
+        "build" => Some(CardAction::Build),
+        "move" => Some(CardAction::Move),
+        "betray" => Some(CardAction::Betray),
+        "battle" => Some(CardAction::Battle),
+        _ => None,
+    }
+}
+
+/// Abilities are written as their full name, in lowercase, with words
+/// separated by single spaces
+fn parse_ability(name: &str) -> Option<SpecialAbility> {
+    use SpecialAbility::*;
+
+    match name {
+        "strange bedfellows" => Some(StrangeBedfellows),
+        "coalition influence" => Some(CoalitionInfluence),
+        "savvy operator" => Some(SavvyOperator),
+        "irregulars" => Some(Irregulars),
+        "citadel" => Some(Citadel),
+        "charismatic courtiers" => Some(CharismaticCourtiers),
+        "safe house" => Some(SafeHouse),
+        "infrastructure" => Some(Infrastructure),
+        "bodyguards" => Some(Bodyguards),
+        "region influence" => Some(RegionInfluence),
+        "blackmail" => Some(Blackmail),
+        "indispensable advisors" => Some(IndispensableAdvisors),
+        "insurrection" => Some(Insurrection),
+        "civil service reforms" => Some(CivilServiceReforms),
+        "claim of ancient lineage" => Some(ClaimOfAncientLineage),
+        "indian supplies" => Some(IndianSupplies),
+        "well connected" => Some(WellConnected),
+        _ => None,
+    }
+}
+
+/// Cursor over the tokens of a single line. Errors are reported as a column
+/// and a kind; the line number is attached by the caller.
+struct LineParser<'a> {
+    tokens: std::vec::IntoIter<Token<'a>>,
+    end_column: usize,
+}
+
+type LineError = (usize, ParseErrorKind);
+
+impl<'a> LineParser<'a> {
+    fn next(&mut self, expected: &'static str) -> Result<Token<'a>, LineError> {
+        self.tokens
+            .next()
+            .ok_or((self.end_column, ParseErrorKind::UnexpectedEnd { expected }))
+    }
+
+    fn expect<T>(
+        &mut self,
+        expected: &'static str,
+        parse: impl FnOnce(&str) -> Option<T>,
+    ) -> Result<T, LineError> {
+        let token = self.next(expected)?;
+        parse(token.text).ok_or_else(|| {
+            (
+                token.column,
+                ParseErrorKind::Expected {
+                    expected,
+                    found: token.text.to_owned(),
+                },
+            )
+        })
+    }
+}
+
+fn malformed(token: Token<'_>, reason: &'static str) -> LineError {
+    (token.column, ParseErrorKind::MalformedImpact(reason))
+}
+
+/// Count one more piece placed by a card's impact
+fn add_piece(count: &mut i8, token: Token<'_>) -> Result<(), LineError> {
+    *count = count
+        .checked_add(1)
+        .ok_or_else(|| malformed(token, "too many pieces of one kind"))?;
+    Ok(())
+}
+
+/// Parse a single, non-empty, non-comment line into a card
+fn parse_line(line: &str) -> Result<CardData<'_>, LineError> {
+    let colon = line
+        .find(':')
+        .ok_or((line.chars().count() + 1, ParseErrorKind::MissingColon))?;
+
+    let name = line[..colon].trim();
+    if name.is_empty() {
+        return Err((1, ParseErrorKind::MissingName));
+    }
+
+    let offset = line[..=colon].chars().count();
+    let mut parser = LineParser {
+        tokens: tokenize(&line[colon + 1..], offset).into_iter(),
+        end_column: line.chars().count() + 1,
+    };
+
+    let suit = parser.expect("a suit", parse_suit)?;
+    let rank = parser.expect("a rank of 1, 2 or 3", parse_rank)?;
+    parser.expect("'star'", |word| {
+        (word.eq_ignore_ascii_case("star") || word.eq_ignore_ascii_case("stars")).then_some(())
+    })?;
+    let region = parser.expect("a region", parse_region)?;
+
+    let mut card = CardData {
+        name,
+        rank,
+        suit,
+        region,
+        patriot: None,
+        prize: None,
+        impact: Impact {
+            armies: 0,
+            roads: 0,
+            spies: 0,
+            tribes: 0,
+            leverage: false,
+            favor: None,
+        },
+        actions: ActionSet {
+            tax: false,
+            gift: false,
+            build: false,
+            movement: false,
+            betray: false,
+            battle: false,
+        },
+        ability: None,
+    };
+
+    // Impacts, up to the comma
+    loop {
+        let token = parser.next("an impact or ','")?;
+
+        if token.is(",") {
+            break;
+        } else if token.is("army") {
+            add_piece(&mut card.impact.armies, token)?;
+        } else if token.is("road") {
+            add_piece(&mut card.impact.roads, token)?;
+        } else if token.is("spy") {
+            add_piece(&mut card.impact.spies, token)?;
+        } else if token.is("tribe") {
+            add_piece(&mut card.impact.tribes, token)?;
+        } else if token.is("leverage") {
+            if card.impact.leverage {
+                return Err(malformed(token, "the card already has leverage"));
+            }
+            card.impact.leverage = true;
+        } else if let Some(coalition) = parse_coalition(token.text) {
+            let qualifier = parser.next("'patriot' or 'prize'")?;
+
+            let slot = if qualifier.is("patriot") {
+                &mut card.patriot
+            } else if qualifier.is("prize") {
+                &mut card.prize
+            } else {
+                return Err(malformed(
+                    qualifier,
+                    "a coalition must be followed by 'patriot' or 'prize'",
+                ));
+            };
+
+            if slot.replace(coalition).is_some() {
+                return Err(malformed(
+                    token,
+                    "the card already has a coalition for this icon",
+                ));
+            }
+        } else if let Some(suit) = parse_suit(token.text) {
+            let qualifier = parser.next("'favor'")?;
+
+            if !qualifier.is("favor") {
+                return Err(malformed(qualifier, "a suit must be followed by 'favor'"));
+            }
+
+            if card.impact.favor.replace(suit).is_some() {
+                return Err(malformed(token, "the card already has a favored suit"));
+            }
+        } else {
+            return Err((
+                token.column,
+                ParseErrorKind::UnknownKeyword(token.text.to_owned()),
+            ));
+        }
+    }
+
+    // Actions, up to the semicolon
+    let mut ability_column = None;
+
+    for token in parser.tokens.by_ref() {
+        if token.is(";") {
+            ability_column = Some(token.column);
+            break;
+        }
+
+        let action = parse_action(token.text).ok_or_else(|| {
+            (
+                token.column,
+                ParseErrorKind::UnknownKeyword(token.text.to_owned()),
+            )
+        })?;
+
+        let slot = match action {
+            CardAction::Tax => &mut card.actions.tax,
+            CardAction::Gift => &mut card.actions.gift,
+            CardAction::Build => &mut card.actions.build,
+            CardAction::Move => &mut card.actions.movement,
+            CardAction::Betray => &mut card.actions.betray,
+            CardAction::Battle => &mut card.actions.battle,
+        };
+
+        if std::mem::replace(slot, true) {
+            return Err((token.column, ParseErrorKind::DuplicateAction(action)));
+        }
+    }
+
+    // The special ability is everything after the semicolon
+    if ability_column.is_some() {
+        let words: Vec<Token<'_>> = parser.tokens.by_ref().collect();
+        let first = words.first().ok_or((
+            parser.end_column,
+            ParseErrorKind::UnexpectedEnd {
+                expected: "a special ability",
+            },
+        ))?;
+
+        let name = words
+            .iter()
+            .map(|token| token.text.to_ascii_lowercase())
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        card.ability = Some(
+            parse_ability(&name)
+                .ok_or((first.column, ParseErrorKind::UnknownKeyword(name.clone())))?,
+        );
+    }
+
+    Ok(card)
+}
+
+/// Parse a card list, in the format used by `cards/cardlist`. Each line
+/// describes a single court card:
+///
+/// ```text
+/// Money Lenders: economic 2 star kabul road road leverage, gift build
+/// Mohan Lal: intelligence 1 star kabul british patriot spy, move betray; charismatic courtiers
+/// ```
+///
+/// The name is everything before the colon. It's followed by the suit, rank
+/// and region, and then the card's icons: `army`, `road`, `spy`, `tribe`,
+/// `leverage`, `<suit> favor`, `<coalition> patriot` and `<coalition> prize`,
+/// where coalitions are written as `british`, `russian` or `afghan`. After a
+/// comma come the card's actions, and after an optional semicolon, its special
+/// ability. Blank lines and lines starting with `#` are ignored.
+///
+/// Card names borrow from the input, so the cards can be loaded either at
+/// build time (via `include_str!`) or from a file at runtime.
+pub fn parse_cardlist(input: &str) -> Result<Vec<CardData<'_>>, ParseError> {
+    let mut cards = Vec::new();
+    let mut names: HashMap<String, usize> = HashMap::new();
+
+    for (index, line) in input.lines().enumerate() {
+        let line_number = index + 1;
+        let trimmed = line.trim();
+
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+
+        let card = parse_line(line).map_err(|(column, kind)| ParseError {
+            line: line_number,
+            column,
+            kind,
+        })?;
+
+        if let Some(&first_line) = names.get(&card.name.to_lowercase()) {
+            return Err(ParseError {
+                line: line_number,
+                column: line
+                    .find(card.name)
+                    .map_or(1, |index| line[..index].chars().count() + 1),
+                kind: ParseErrorKind::DuplicateName {
+                    name: card.name.to_owned(),
+                    first_line,
+                },
+            });
+        }
+
+        names.insert(card.name.to_lowercase(), line_number);
+        cards.push(card);
+    }
+
+    Ok(cards)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::cards::list::all_court_cards;
+
+    fn parse_error(input: &str) -> ParseError {
+        parse_cardlist(input).expect_err("input should not parse")
+    }
+
+    #[test]
+    fn test_basic_card() {
+        let cards =
+            parse_cardlist("Money Lenders: economic 2 star kabul road road leverage, gift build")
+                .unwrap();
+
+        assert_eq!(cards.len(), 1);
+        let card = &cards[0];
+
+        assert_eq!(card.name, "Money Lenders");
+        assert_eq!(card.suit, Suit::Economic);
+        assert_eq!(card.rank, Rank::Two);
+        assert_eq!(card.region, Region::Kabul);
+        assert_eq!(card.impact.roads, 2);
+        assert!(card.impact.leverage);
+        assert!(card.actions.gift);
+        assert!(card.actions.build);
+        assert_eq!(card.actions.count(), 2);
+        assert_eq!(card.ability, None);
+    }
+
+    #[test]
+    fn test_loyalty_and_ability() {
+        let cards = parse_cardlist(
+            "# A comment\n\nMohan Lal: intelligence 1 star kabul british patriot \
+            russian prize spy political favor, move betray; Charismatic Courtiers\n",
+        )
+        .unwrap();
+
+        let card = &cards[0];
+        assert_eq!(card.patriot, Some(Coalition::Britain));
+        assert_eq!(card.prize, Some(Coalition::Russia));
+        assert_eq!(card.impact.spies, 1);
+        assert_eq!(card.impact.favor, Some(Suit::Political));
+        assert_eq!(card.ability, Some(SpecialAbility::CharismaticCourtiers));
+    }
+
+    #[test]
+    fn test_cardlist_matches_catalogue() {
+        let parsed = parse_cardlist(include_str!("../../cards/cardlist")).unwrap();
+        let catalogue: Vec<CardData<'static>> =
+            all_court_cards().map(|card| (*card).clone()).collect();
+
+        assert_eq!(parsed, catalogue);
+    }
+
+    #[test]
+    fn test_unknown_keyword() {
+        assert_eq!(
+            parse_error("Foo: economic 2 star kabul road bridge, gift"),
+            ParseError {
+                line: 1,
+                column: 33,
+                kind: ParseErrorKind::UnknownKeyword("bridge".to_owned()),
+            }
+        );
+    }
+
+    #[test]
+    fn test_unknown_action() {
+        assert_eq!(
+            parse_error("Foo: economic 2 star kabul road, gift trade"),
+            ParseError {
+                line: 1,
+                column: 39,
+                kind: ParseErrorKind::UnknownKeyword("trade".to_owned()),
+            }
+        );
+    }
+
+    #[test]
+    fn test_unknown_ability() {
+        assert_eq!(
+            parse_error("Foo: economic 2 star kabul road, gift; free   lunch"),
+            ParseError {
+                line: 1,
+                column: 40,
+                kind: ParseErrorKind::UnknownKeyword("free lunch".to_owned()),
+            }
+        );
+    }
+
+    #[test]
+    fn test_bad_rank() {
+        assert_eq!(
+            parse_error("\nFoo: economic 4 star kabul road, gift"),
+            ParseError {
+                line: 2,
+                column: 15,
+                kind: ParseErrorKind::Expected {
+                    expected: "a rank of 1, 2 or 3",
+                    found: "4".to_owned()
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn test_missing_actions() {
+        assert_eq!(
+            parse_error("Foo: economic 2 star kabul road"),
+            ParseError {
+                line: 1,
+                column: 32,
+                kind: ParseErrorKind::UnexpectedEnd {
+                    expected: "an impact or ','"
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn test_missing_colon() {
+        assert_eq!(
+            parse_error("Foo economic"),
+            ParseError {
+                line: 1,
+                column: 13,
+                kind: ParseErrorKind::MissingColon,
+            }
+        );
+    }
+
+    #[test]
+    fn test_duplicate_name() {
+        assert_eq!(
+            parse_error(
+                "Foo: economic 2 star kabul road, gift\n\n  foo: military 1 star herat army, battle"
+            ),
+            ParseError {
+                line: 3,
+                column: 3,
+                kind: ParseErrorKind::DuplicateName {
+                    name: "foo".to_owned(),
+                    first_line: 1,
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn test_dangling_coalition() {
+        assert_eq!(
+            parse_error("Foo: economic 2 star kabul british road, gift"),
+            ParseError {
+                line: 1,
+                column: 36,
+                kind: ParseErrorKind::MalformedImpact(
+                    "a coalition must be followed by 'patriot' or 'prize'"
+                ),
+            }
+        );
+    }
+
+    #[test]
+    fn test_duplicate_patriot() {
+        assert_eq!(
+            parse_error("Foo: economic 2 star kabul british patriot afghan patriot, gift"),
+            ParseError {
+                line: 1,
+                column: 44,
+                kind: ParseErrorKind::MalformedImpact(
+                    "the card already has a coalition for this icon"
+                ),
+            }
+        );
+    }
+
+    #[test]
+    fn test_too_many_pieces() {
+        let line = format!("Foo: economic 2 star kabul{}, gift", " army".repeat(128));
+
+        // The 128th army is one too many
+        assert_eq!(
+            parse_error(&line),
+            ParseError {
+                line: 1,
+                column: 28 + 127 * 5,
+                kind: ParseErrorKind::MalformedImpact("too many pieces of one kind"),
+            }
+        );
+    }
+
+    #[test]
+    fn test_dangling_favor() {
+        assert_eq!(
+            parse_error("Foo: economic 2 star kabul military, gift"),
+            ParseError {
+                line: 1,
+                column: 36,
+                kind: ParseErrorKind::MalformedImpact("a suit must be followed by 'favor'"),
+            }
+        );
+    }
+
+    #[test]
+    fn test_duplicate_action() {
+        assert_eq!(
+            parse_error("Foo: economic 2 star kabul road, gift tax gift"),
+            ParseError {
+                line: 1,
+                column: 43,
+                kind: ParseErrorKind::DuplicateAction(CardAction::Gift),
+            }
+        );
+    }
+}
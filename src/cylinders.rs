@@ -1,9 +1,11 @@
+use std::{cmp, mem};
+
 use enum_map::EnumMap;
 use serde::{Deserialize, Serialize};
 
 use crate::player::{self};
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
 #[serde(transparent)]
 pub struct SingleCylinderSet {
     count: i8,
@@ -27,6 +29,18 @@ impl SingleCylinderSet {
         self.count += source.count
     }
 
+    /// Take up to `count` cylinders out of this set and return them
+    pub fn take_up_to(&mut self, count: i8) -> Self {
+        let count = cmp::min(count, self.count);
+        self.count -= count;
+        Self { count }
+    }
+
+    /// Remove all cylinders from this set and return them
+    pub fn take_all(&mut self) -> Self {
+        mem::take(self)
+    }
+
     pub fn merge(self, rhs: Self) -> Self {
         Self {
             count: self.count + rhs.count,
@@ -34,7 +48,7 @@ impl SingleCylinderSet {
     }
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
 pub struct CylinderSet {
     bank: EnumMap<player::Color, SingleCylinderSet>,
 }
@@ -47,4 +61,25 @@ impl CylinderSet {
     pub fn count(&self, player: player::Color) -> i8 {
         self.bank[player].count()
     }
+
+    /// The total number of cylinders in this set, across all players
+    pub fn total(&self) -> i8 {
+        self.bank.values().map(|set| set.count()).sum()
+    }
+
+    /// Add some of a player's cylinders to this set
+    pub fn add(&mut self, player: player::Color, cylinders: SingleCylinderSet) {
+        self.bank[player].add(cylinders)
+    }
+
+    /// Take up to `count` of a player's cylinders out of this set and return
+    /// them
+    pub fn take_up_to(&mut self, player: player::Color, count: i8) -> SingleCylinderSet {
+        self.bank[player].take_up_to(count)
+    }
+
+    /// Remove all of a player's cylinders from this set and return them
+    pub fn take(&mut self, player: player::Color) -> SingleCylinderSet {
+        self.bank[player].take_all()
+    }
 }
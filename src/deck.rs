@@ -1,67 +1,374 @@
-use std::iter;
+use std::collections::HashSet;
 
 use itertools::Itertools;
 use rand::{prelude::SliceRandom, Rng};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
 use crate::cards::{
     self, event,
-    list::{all_court_cards, all_event_cards},
-    Card,
+    list::{all_court_cards, all_dominance_cards, all_event_cards},
+    Card, CardId,
 };
 
+/// The draw deck. Cards are drawn from the top, one at a time.
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct Deck {
     // The back of the vec is the top of the deck
     cards: Vec<Card>,
 }
 
-fn build_deck(rng: &mut impl Rng, player_count: usize) -> Deck {
-    let mut court_cards = all_court_cards().collect_vec();
-    let mut event_cards = all_event_cards().collect_vec();
-
-    // Shuffle court cards and event cards
-    court_cards.shuffle(rng);
-    event_cards.shuffle(rng);
-
-    let mut court_cards = court_cards.into_iter();
-    let mut event_cards = event_cards.into_iter();
-
-    let mut piles: [Vec<cards::Card>; 6] = brownstone::build(|| Vec::with_capacity(12));
-    let pile_size = 5 + player_count;
-
-    // Deal court cards
-    piles
-        .iter_mut()
-        .for_each(|pile| pile.extend(court_cards.by_ref().take(pile_size).map(cards::Card::Court)));
-
-    // Add 2 event cards to the second pile
-    piles[5].extend(
-        event_cards
-            .by_ref()
-            .take(2)
-            .map(event::Card::Event)
-            .map(cards::Card::Event),
-    );
-
-    // Add a dominance card and event card to the back 4 piles
-    piles[..4].iter_mut().for_each(|pile| {
-        pile.extend(
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Error)]
+#[error("card {} ({}) appears more than once", .0.number(), .0.name())]
+pub struct DuplicateCard(pub CardId);
+
+/// A problem with the cards stacked on top of a deck
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Error)]
+pub enum StackError {
+    #[error(transparent)]
+    Duplicate(#[from] DuplicateCard),
+
+    #[error("{} wasn't dealt into the deck", .0.name())]
+    NotDealt(CardId),
+}
+
+/// Check that a list of card ids has no duplicates
+fn check_unique(ids: &[CardId]) -> Result<(), DuplicateCard> {
+    let mut seen = HashSet::with_capacity(ids.len());
+
+    match ids.iter().find(|&&id| !seen.insert(id)) {
+        Some(&id) => Err(DuplicateCard(id)),
+        None => Ok(()),
+    }
+}
+
+/// The number of court cards in each pile of the deck
+fn pile_size(player_count: usize) -> usize {
+    5 + player_count
+}
+
+impl Deck {
+    /// Build a new deck for a game with the given number of players, following
+    /// the rulebook setup: six piles of court cards, two event cards in the
+    /// second pile, and an event card and a dominance check in each of the
+    /// bottom four piles. Each pile is shuffled separately and then stacked.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `player_count` isn't between 1 and 5.
+    pub fn new(rng: &mut impl Rng, player_count: usize) -> Self {
+        assert!(
+            (1..=5).contains(&player_count),
+            "invalid player count: {}",
+            player_count
+        );
+
+        let mut court_cards = all_court_cards().collect_vec();
+        let mut event_cards = all_event_cards().collect_vec();
+
+        // Shuffle court cards and event cards
+        court_cards.shuffle(rng);
+        event_cards.shuffle(rng);
+
+        let mut court_cards = court_cards.into_iter();
+        let mut event_cards = event_cards.into_iter();
+        let mut dominance_cards = all_dominance_cards();
+
+        // piles[0] is the bottom pile; piles[5] is the top
+        let mut piles: [Vec<cards::Card>; 6] = brownstone::build(|| Vec::with_capacity(12));
+        let pile_size = pile_size(player_count);
+
+        // Deal court cards
+        piles.iter_mut().for_each(|pile| {
+            pile.extend(court_cards.by_ref().take(pile_size).map(cards::Card::Court))
+        });
+
+        // Add 2 event cards to the second pile
+        piles[4].extend(
             event_cards
                 .by_ref()
-                .take(1)
+                .take(2)
                 .map(event::Card::Event)
-                .chain(iter::once(event::Card::Dominance))
                 .map(cards::Card::Event),
-        )
-    });
+        );
+
+        // Add a dominance card and event card to the back 4 piles
+        piles[..4].iter_mut().for_each(|pile| {
+            pile.extend(
+                event_cards
+                    .by_ref()
+                    .take(1)
+                    .map(event::Card::Event)
+                    .chain(dominance_cards.by_ref().take(1))
+                    .map(cards::Card::Event),
+            )
+        });
+
+        Deck {
+            cards: piles
+                .into_iter()
+                .flat_map(|mut pile| {
+                    pile.shuffle(rng);
+                    pile
+                })
+                .collect(),
+        }
+    }
+
+    /// Build a deck containing exactly the given cards, in order. The first
+    /// card will be the first one drawn. Useful for reproducing a game or for
+    /// scripted scenarios.
+    pub fn from_ids(ids: impl IntoIterator<Item = CardId>) -> Result<Self, DuplicateCard> {
+        let ids = ids.into_iter().collect_vec();
+        check_unique(&ids)?;
+
+        Ok(Deck {
+            cards: ids.into_iter().rev().map(Card::from_id).collect(),
+        })
+    }
+
+    /// Build a normal deck (as with [`Deck::new`]), but with the given cards
+    /// stacked on top, in order, so that the first card is the first one
+    /// drawn. Each stacked card is pulled out of the deck if it was dealt
+    /// into it; stacked court cards that didn't make it into the deck are
+    /// added to it. Event and dominance cards can only be stacked if they
+    /// were dealt, so the deck never gains extra events.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `player_count` isn't between 1 and 5.
+    pub fn stacked(
+        rng: &mut impl Rng,
+        player_count: usize,
+        top: impl IntoIterator<Item = CardId>,
+    ) -> Result<Self, StackError> {
+        let top = top.into_iter().collect_vec();
+        check_unique(&top)?;
+
+        let mut deck = Self::new(rng, player_count);
+        let dealt: HashSet<CardId> = deck.cards.iter().map(|card| card.id()).collect();
+
+        if let Some(&id) = top
+            .iter()
+            .find(|&id| !dealt.contains(id) && !matches!(Card::from_id(*id), Card::Court(_)))
+        {
+            return Err(StackError::NotDealt(id));
+        }
+
+        let stacked: HashSet<CardId> = top.iter().copied().collect();
+
+        deck.cards.retain(|card| !stacked.contains(&card.id()));
+        deck.cards.extend(top.into_iter().rev().map(Card::from_id));
+
+        Ok(deck)
+    }
+
+    /// The number of cards left in the deck
+    pub fn len(&self) -> usize {
+        self.cards.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cards.is_empty()
+    }
+
+    /// The number of dominance checks still in the deck
+    pub fn dominance_count(&self) -> usize {
+        self.cards.iter().filter(|card| card.is_dominance()).count()
+    }
+
+    /// Draw the top card of the deck
+    pub fn draw(&mut self) -> Option<Card> {
+        self.cards.pop()
+    }
+
+    /// Draw up to `count` cards from the top of the deck, in the order they're
+    /// drawn
+    pub fn draw_n(&mut self, count: usize) -> Vec<Card> {
+        self.draws().take(count).collect()
+    }
+
+    /// Get an iterator that draws cards from the top of the deck as it's
+    /// advanced. Useful for dealing into the market:
+    /// `market.fill_from(&mut deck.draws())`
+    pub fn draws(&mut self) -> Draws<'_> {
+        Draws { deck: self }
+    }
+}
+
+/// Iterator that draws cards from a [`Deck`]. See [`Deck::draws`].
+#[derive(Debug)]
+pub struct Draws<'a> {
+    deck: &'a mut Deck,
+}
+
+impl Iterator for Draws<'_> {
+    type Item = Card;
+
+    fn next(&mut self) -> Option<Card> {
+        self.deck.draw()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.deck.len(), Some(self.deck.len()))
+    }
+}
+
+impl ExactSizeIterator for Draws<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    Deck {
-        cards: piles
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use crate::{cards::CardKind, market::Market};
+
+    /// Split a deck into its piles, from bottom to top
+    fn piles(deck: &Deck, player_count: usize) -> Vec<&[Card]> {
+        let pile_size = pile_size(player_count);
+        let mut cards = deck.cards.as_slice();
+
+        [2, 2, 2, 2, 2, 0]
             .into_iter()
-            .map(|mut pile| {
-                pile.shuffle(rng);
+            .map(|extra| {
+                let (pile, rest) = cards.split_at(pile_size + extra);
+                cards = rest;
                 pile
             })
-            .flatten()
-            .collect(),
+            .collect()
+    }
+
+    fn count_kind(pile: &[Card], kind: CardKind) -> usize {
+        pile.iter().filter(|card| card.id().kind() == kind).count()
+    }
+
+    #[test]
+    fn test_deck_size() {
+        for player_count in 1..=5 {
+            let deck = Deck::new(&mut StdRng::seed_from_u64(1), player_count);
+
+            assert_eq!(deck.len(), 6 * pile_size(player_count) + 10);
+            assert_eq!(deck.dominance_count(), 4);
+        }
+    }
+
+    #[test]
+    fn test_pile_contents() {
+        for player_count in 1..=5 {
+            for seed in 0..10 {
+                let deck = Deck::new(&mut StdRng::seed_from_u64(seed), player_count);
+                let piles = piles(&deck, player_count);
+                let court_cards = pile_size(player_count);
+
+                for pile in &piles[..4] {
+                    assert_eq!(count_kind(pile, CardKind::Court), court_cards);
+                    assert_eq!(count_kind(pile, CardKind::Event), 1);
+                    assert_eq!(count_kind(pile, CardKind::Dominance), 1);
+                }
+
+                assert_eq!(count_kind(piles[4], CardKind::Court), court_cards);
+                assert_eq!(count_kind(piles[4], CardKind::Event), 2);
+                assert_eq!(count_kind(piles[4], CardKind::Dominance), 0);
+
+                assert_eq!(count_kind(piles[5], CardKind::Court), court_cards);
+                assert_eq!(piles[5].len(), court_cards);
+            }
+        }
+    }
+
+    #[test]
+    fn test_draw() {
+        let mut deck = Deck::new(&mut StdRng::seed_from_u64(1), 2);
+        let top = deck.cards.last().unwrap().id();
+
+        assert_eq!(deck.draw().unwrap().id(), top);
+        assert_eq!(deck.len(), 51);
+
+        assert_eq!(deck.draw_n(5).len(), 5);
+        assert_eq!(deck.len(), 46);
+
+        assert_eq!(deck.draw_n(100).len(), 46);
+        assert!(deck.is_empty());
+        assert!(deck.draw().is_none());
+    }
+
+    fn ids(names: &[&str]) -> Vec<CardId> {
+        names
+            .iter()
+            .map(|name| CardId::from_name(name).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_from_ids() {
+        let order = ids(&["Money Lenders", "Rumor", "Ranjit Singh"]);
+        let mut deck = Deck::from_ids(order.clone()).unwrap();
+
+        assert_eq!(deck.len(), 3);
+        assert_eq!(deck.draws().map(|card| card.id()).collect_vec(), order);
+    }
+
+    #[test]
+    fn test_from_ids_duplicate() {
+        let order = ids(&["Money Lenders", "Rumor", "money lenders"]);
+
+        assert_eq!(
+            Deck::from_ids(order.clone()).unwrap_err(),
+            DuplicateCard(order[0])
+        );
+    }
+
+    #[test]
+    fn test_stacked() {
+        let dominance = CardId::from_name("Dominance Check").unwrap();
+        let mut top = ids(&["Money Lenders", "Rumor"]);
+        top.insert(1, dominance);
+
+        // With this seed, Rumor is dealt into the deck but Money Lenders isn't
+        let mut deck = Deck::stacked(&mut StdRng::seed_from_u64(4), 2, top.clone()).unwrap();
+
+        // Stacked cards are never duplicated, the dominance check and Rumor
+        // are moved rather than added, and Money Lenders is added
+        assert_eq!(deck.dominance_count(), 4);
+        assert_eq!(
+            deck.cards.iter().map(|card| card.id()).unique().count(),
+            deck.len()
+        );
+        assert_eq!(deck.len(), 6 * pile_size(2) + 10 + 1);
+
+        assert_eq!(
+            deck.draw_n(3).iter().map(|card| card.id()).collect_vec(),
+            top
+        );
+    }
+
+    #[test]
+    fn test_stacked_event_not_dealt() {
+        // With this seed, Rumor isn't dealt into the deck
+        let top = ids(&["Money Lenders", "Rumor"]);
+
+        assert_eq!(
+            Deck::stacked(&mut StdRng::seed_from_u64(3), 2, top.clone()).unwrap_err(),
+            StackError::NotDealt(top[1])
+        );
+    }
+
+    #[test]
+    fn test_stacked_is_deterministic() {
+        let top = ids(&["Rumor"]);
+        let first = Deck::stacked(&mut StdRng::seed_from_u64(4), 4, top.clone()).unwrap();
+        let second = Deck::stacked(&mut StdRng::seed_from_u64(4), 4, top).unwrap();
+
+        assert_eq!(first.cards, second.cards);
+    }
+
+    #[test]
+    fn test_fill_market() {
+        let mut deck = Deck::new(&mut StdRng::seed_from_u64(1), 3);
+        let mut market = Market::new();
+
+        market.fill_from(&mut deck.draws());
+        assert_eq!(deck.len(), 6 * pile_size(3) + 10 - 12);
     }
 }
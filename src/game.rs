@@ -1,20 +1,48 @@
+mod action;
+mod battle;
+mod build;
 mod dominance;
 mod end_turn;
 mod event;
+mod legal;
+mod movement;
+mod pricing;
+mod request;
+mod setup;
+#[cfg(test)]
+mod test_util;
+mod tribes;
+
+use std::{cmp, collections::VecDeque};
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+pub use action::{
+    Action, ActionError, BattleSite, BattleTarget, BuildTarget, EventChoice, GameEvent, Movement,
+    TaxSource, ACTIONS_PER_TURN,
+};
+pub use dominance::GameOver;
+pub use end_turn::Discards;
+pub use pricing::{Bribe, Pricing, BETRAY_COST, BUILD_COST, MAX_BUILDS, MAX_GIFTS};
+pub use request::{Response, ResponseError};
+pub use setup::SetupError;
+pub use tribes::TribeError;
 
 use crate::{
     blocks::BlockSet,
-    cards::Card,
-    map::Map,
+    cards::{court, Card, CardId},
+    deck::Deck,
+    map::{Map, Region},
     market::Market,
-    player::{self, PlayerSet, PlayerState},
-    primitives::Suit,
+    player::{Color, CourtCard, Player, PlayerSet},
+    primitives::{Coalition, Suit},
 };
 
 /// Permanent game effects (triggered by event cards). All of these are
 /// reset by a Dominance Check
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct Effects {
     /// Coalitions only require 2 blocks for dominance
     pub conflict_fatigue: bool,
@@ -40,18 +68,117 @@ impl Effects {
 }
 
 /// The state of the current player's turn
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct TurnState {
     pub player: usize,
     pub actions_taken: i8,
+
+    /// The player has ended their turn, which finishes once any debts from
+    /// their discards are paid
+    pub ending: bool,
+}
+
+/// A decision a player has to make before normal play can continue
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Decision {
+    /// Confidence Failure was discarded from the market, so the player must
+    /// discard a card from their hand
+    ConfidenceFailure,
+
+    /// A card with leverage left the player's court and they couldn't pay
+    /// the rupees back, so they must discard a card from their hand or court
+    /// instead
+    LeverageDebt,
+
+    /// An Insurrection has to place armies, but the supply ran out. The
+    /// player must choose which blocks of the coalition to move off of the
+    /// map and into the region instead.
+    InsurrectionArmies {
+        region: Region,
+        coalition: Coalition,
+        count: i8,
+    },
+}
+
+/// A decision, along with the player who has to make it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Request {
+    pub player: Color,
+    pub decision: Decision,
 }
 
 /// The request queue is the set of player decisions that need to be played
-/// before normal play can continue.
-#[derive(Debug)]
-pub struct RequestQueue {}
+/// before normal play can continue. Each player answers their own requests
+/// in order, but players don't have to wait for each other.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct RequestQueue {
+    requests: VecDeque<Request>,
+}
+
+impl RequestQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a request to the back of the queue
+    pub fn push(&mut self, request: Request) {
+        self.requests.push_back(request)
+    }
+
+    /// The request that has to be answered next
+    pub fn front(&self) -> Option<&Request> {
+        self.requests.front()
+    }
+
+    /// Every player with an outstanding request for a kind of decision, in
+    /// the order they were asked. A networked game can use this to see who
+    /// it's still waiting on, such as the players who owe a discard for
+    /// Confidence Failure.
+    pub fn players_owing(&self, decision: Decision) -> Vec<Color> {
+        self.requests
+            .iter()
+            .filter(|request| request.decision == decision)
+            .map(|request| request.player)
+            .unique()
+            .collect()
+    }
+
+    /// The next request a player has to answer, if any
+    pub fn next_for(&self, player: Color) -> Option<&Request> {
+        self.requests
+            .iter()
+            .find(|request| request.player == player)
+    }
 
-#[derive(Debug)]
+    /// Remove the next request a player has to answer, once it's answered
+    fn take_next_for(&mut self, player: Color) -> Option<Request> {
+        let index = self
+            .requests
+            .iter()
+            .position(|request| request.player == player)?;
+
+        self.requests.remove(index)
+    }
+
+    /// Drop every outstanding request, once the game is over
+    fn clear(&mut self) {
+        self.requests.clear()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.requests.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.requests.len()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Request> {
+        self.requests.iter()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Game {
     /// The actual map of afghanistan
     pub map: Map,
@@ -59,6 +186,9 @@ pub struct Game {
     /// The market of cards available for purchase
     pub market: Market,
 
+    /// The draw deck, which refills the market
+    pub deck: Deck,
+
     /// The current favored suit
     pub climate: Suit,
 
@@ -78,6 +208,13 @@ pub struct Game {
     /// Information about the current turn- whose turn it is, how many actions
     /// they have, etc
     pub turn: TurnState,
+
+    /// Decisions players have to make before play can continue
+    pub requests: RequestQueue,
+
+    /// How the game ended, once a dominance check has decided it. No more
+    /// actions or responses are accepted after that.
+    pub game_over: Option<GameOver>,
 }
 
 impl Game {
@@ -88,4 +225,249 @@ impl Game {
             self.climate = climate
         }
     }
+
+    /// The player whose turn it is
+    pub fn current_player(&self) -> &Player {
+        &self.players[self.turn.player]
+    }
+
+    pub fn current_player_mut(&mut self) -> &mut Player {
+        &mut self.players[self.turn.player]
+    }
+
+    /// Find the player whose court a card is in
+    pub fn court_owner(&self, card: CardId) -> Option<Color> {
+        self.players
+            .iter()
+            .find(|player| player.state.court.position(card).is_some())
+            .map(|player| player.color)
+    }
+
+    /// Find a card in any player's court
+    pub fn court_card(&self, card: CardId) -> Option<&CourtCard> {
+        self.players
+            .iter()
+            .find_map(|player| player.state.court.get(card))
+    }
+
+    /// Remove a card from whichever court it's in. Any spies on it are
+    /// returned to their owners.
+    fn remove_court_card(&mut self, card: CardId) -> Option<court::Card> {
+        let owner = self.court_owner(card)?;
+        let court = &mut self.players[owner].state.court;
+        let index = court.position(card)?;
+        let (card, mut spies) = court.cards.remove(index)?.into_parts();
+
+        self.players
+            .iter_mut()
+            .for_each(|player| player.state.discard_cylinders(spies.take(player.color)));
+
+        Some(card)
+    }
+
+    /// Discard a card from a player's court, and resolve losing it
+    fn discard_court_card(&mut self, player: Color, card: CardId, events: &mut Vec<GameEvent>) {
+        if let Some(discarded) = self.remove_court_card(card) {
+            events.push(GameEvent::CardDiscarded { player, card });
+            self.court_card_lost(player, &discarded, events);
+            self.discard.push(Card::Court(discarded));
+        }
+    }
+
+    /// Resolve a card leaving a player's court. A card with leverage has to
+    /// be paid back, with a card from the player's hand or court for every
+    /// rupee they can't pay. A player left with no political cards of a
+    /// region loses their tribes there.
+    fn court_card_lost(&mut self, player: Color, card: &court::Card, events: &mut Vec<GameEvent>) {
+        let state = &mut self.players[player].state;
+
+        if card.impact.leverage {
+            let amount = state.rupees.take_up_to(action::LEVERAGE).count();
+            events.push(GameEvent::LeverageRepaid {
+                player,
+                card: card.id(),
+                amount,
+            });
+
+            let cards_left = state.hand.cards.len() + state.court.cards.len();
+            let debt = cmp::min((action::LEVERAGE - amount) as usize, cards_left);
+            (0..debt).for_each(|_| {
+                self.requests.push(Request {
+                    player,
+                    decision: Decision::LeverageDebt,
+                })
+            });
+        }
+
+        let region = card.region;
+        let still_political = self.players[player]
+            .state
+            .court
+            .cards
+            .iter()
+            .any(|other| other.region == region && other.suit == Suit::Political);
+
+        if card.suit == Suit::Political && !still_political {
+            let count = self.map.tribes(region).count(player);
+
+            if count > 0 {
+                let tribes = self.map.remove_tribes(region, player, count);
+                self.players[player].state.discard_cylinders(tribes);
+                events.push(GameEvent::TribesRemoved {
+                    player,
+                    region,
+                    count,
+                });
+            }
+        }
+    }
+
+    /// Switch a player to another coalition. Their patriots of the old
+    /// coalition are discarded from court, and their prizes and gifts are
+    /// lost.
+    fn change_loyalty(&mut self, player: Color, coalition: Coalition, events: &mut Vec<GameEvent>) {
+        let old = self.players[player].state.loyalty;
+        let patriots: Vec<CardId> = self.players[player]
+            .state
+            .court
+            .cards
+            .iter()
+            .filter(|card| card.patriot == Some(old))
+            .map(|card| card.id())
+            .collect();
+
+        for card in patriots {
+            self.discard_court_card(player, card, events);
+        }
+
+        let state = &mut self.players[player].state;
+        let prizes = std::mem::take(&mut state.prizes.cards);
+        let gifts = state.gifts.take_all();
+        state.discard_cylinders(gifts);
+        state.loyalty = coalition;
+
+        self.discard.extend(prizes.into_iter().map(Card::Court));
+        events.push(GameEvent::LoyaltyChanged { player, coalition });
+    }
+
+    /// Resolve a player losing their last tribe in a region: every political
+    /// card of that region in their court is discarded
+    fn overthrow(&mut self, player: Color, region: Region, events: &mut Vec<GameEvent>) {
+        let overthrown: Vec<CardId> = self.players[player]
+            .state
+            .court
+            .cards
+            .iter()
+            .filter(|card| card.region == region && card.suit == Suit::Political)
+            .map(|card| card.id())
+            .collect();
+
+        for id in overthrown {
+            if let Some(card) = self.remove_court_card(id) {
+                events.push(GameEvent::CardOverthrown { player, card: id });
+                self.court_card_lost(player, &card, events);
+                self.discard.push(Card::Court(card));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        cards::{list::all_court_cards, CardId},
+        map::Region,
+        player::PlayerInit,
+        primitives::Coalition,
+    };
+
+    fn sample_game() -> Game {
+        let mut court_cards = all_court_cards();
+
+        let mut market = Market::new();
+        let mut dealt = court_cards.by_ref().take(12).map(Card::Court);
+        market.fill_from(&mut dealt);
+
+        let mut players = PlayerSet::new([
+            PlayerInit {
+                color: Color::Red,
+                loyalty: Coalition::Britain,
+                name: "Alice".to_owned(),
+            },
+            PlayerInit {
+                color: Color::Blue,
+                loyalty: Coalition::Russia,
+                name: "Bob".to_owned(),
+            },
+        ])
+        .unwrap();
+
+        let red = &mut players[Color::Red].state;
+        red.court
+            .cards
+            .push_back(CourtCard::new(court_cards.next().unwrap()));
+        red.hand.cards.push(court_cards.next().unwrap());
+
+        let mut map = Map::new();
+        map.add_armies(
+            Region::Kabul,
+            BlockSet::new_tray().take_up_to(3, Coalition::Britain),
+        );
+
+        Game {
+            map,
+            market,
+            deck: Deck::default(),
+            climate: Suit::Political,
+            effects: Effects::default(),
+            blocks: BlockSet::new_tray(),
+            players,
+            discard: vec![
+                Card::from_id(CardId::from_name("Rumor").unwrap()),
+                Card::from_id(CardId::from_name("Dominance Check").unwrap()),
+            ],
+            turn: TurnState {
+                player: 1,
+                actions_taken: 1,
+                ending: false,
+            },
+            requests: RequestQueue::new(),
+            game_over: None,
+        }
+    }
+
+    #[test]
+    fn test_serde_round_trip() {
+        let game = sample_game();
+        let json = serde_json::to_string(&game).unwrap();
+        let restored: Game = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(serde_json::to_string(&restored).unwrap(), json);
+
+        assert_eq!(restored.players[Color::Red].state.hand.cards.len(), 1);
+        assert_eq!(restored.discard[0].name(), "Rumor");
+        assert!(restored.discard[1].is_dominance());
+        assert_eq!(restored.map.total_block_counts()[Coalition::Britain], 3);
+    }
+
+    #[test]
+    fn test_cards_serialize_as_ids() {
+        let game = sample_game();
+        let value = serde_json::to_value(&game).unwrap();
+        let rumor = CardId::from_name("Rumor").unwrap();
+
+        assert_eq!(value["discard"][0], serde_json::json!(rumor.number()));
+    }
+
+    #[test]
+    fn test_reject_misplaced_card() {
+        // A player's hand may only contain court cards
+        let mut value = serde_json::to_value(sample_game()).unwrap();
+        let rumor = CardId::from_name("Rumor").unwrap();
+        value["players"]["players"][0]["state"]["hand"]["cards"][0] = rumor.number().into();
+
+        assert!(serde_json::from_value::<Game>(value).is_err());
+    }
 }
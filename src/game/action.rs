@@ -0,0 +1,2084 @@
+use std::{cmp, collections::HashMap};
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    cards::{
+        self,
+        court::{self, CardAction, SpecialAbility},
+        event, CardId,
+    },
+    map::{Border, BorderError, Region},
+    market::{Column, PurchaseError, Receipt, Row},
+    player::{Color, CourtCard, Effect, PlayerState, Side},
+    primitives::{Coalition, Suit},
+    rupees::RupeeSet,
+};
+
+use super::{
+    end_turn::Discards,
+    pricing::{Bribe, MAX_GIFTS},
+    Game, GameOver, Request,
+};
+
+/// The number of actions a player may take each turn, not counting bonus
+/// actions from favored cards
+pub const ACTIONS_PER_TURN: i8 = 2;
+
+/// The rupees taken for playing a card with leverage, and paid back when it
+/// leaves the court
+pub(super) const LEVERAGE: i8 = 2;
+
+/// Something the current player does on their turn. Cards are always referred
+/// to by id, so actions can be logged and sent over the wire.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Action {
+    /// Buy the card in a market slot. Some event cards need a decision when
+    /// they're bought, which is given in `choice`; it must be `None` for every
+    /// other card.
+    Purchase {
+        row: Row,
+        column: Column,
+        choice: Option<EventChoice>,
+    },
+
+    /// Play a card from hand onto one side of the court. `roads` lists a
+    /// border for each road the card places, and `spies` lists a court card
+    /// for each spy it places. If the supply runs short, only as many as are
+    /// available are placed.
+    Play {
+        card: CardId,
+        side: Side,
+        roads: Vec<Border>,
+        spies: Vec<CardId>,
+    },
+
+    /// Use a court card to take rupees, one from each source
+    Tax {
+        card: CardId,
+        sources: Vec<TaxSource>,
+    },
+
+    /// Use a court card to buy a gift
+    Gift { card: CardId },
+
+    /// Use a court card to place armies and roads
+    Build {
+        card: CardId,
+        targets: Vec<BuildTarget>,
+    },
+
+    /// Use a court card to move pieces around the map or spies between cards
+    Move { card: CardId, moves: Vec<Movement> },
+
+    /// Use a court card to discard a card the player has a spy on, optionally
+    /// keeping it as a prize
+    Betray {
+        card: CardId,
+        target: CardId,
+        take_prize: bool,
+    },
+
+    /// Use a court card to remove enemy pieces at a single site, one per
+    /// target
+    Battle {
+        card: CardId,
+        site: BattleSite,
+        targets: Vec<BattleTarget>,
+    },
+
+    /// End the turn, discarding down to the court and hand limits
+    EndTurn(Discards),
+}
+
+/// The decision made when buying an event card whose purchase effect needs
+/// one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EventChoice {
+    /// The player whose patriots stop counting for influence
+    Rumor(Color),
+
+    /// The player to trade hands with
+    OtherPersuasiveMethods(Color),
+
+    /// The suit that becomes the permanent climate
+    PashtunwaliValues(Suit),
+
+    /// The region to remove every army and tribe from
+    Rebuke(Region),
+}
+
+impl EventChoice {
+    /// Check if this choice is the kind of decision an event needs
+    fn is_for(&self, event: event::PurchaseEvent) -> bool {
+        use event::PurchaseEvent;
+
+        matches!(
+            (self, event),
+            (EventChoice::Rumor(_), PurchaseEvent::Rumor)
+                | (
+                    EventChoice::OtherPersuasiveMethods(_),
+                    PurchaseEvent::OtherPersuasiveMethods
+                )
+                | (
+                    EventChoice::PashtunwaliValues(_),
+                    PurchaseEvent::PashtunwaliValues
+                )
+                | (EventChoice::Rebuke(_), PurchaseEvent::Rebuke)
+        )
+    }
+}
+
+/// Somewhere a rupee can be taxed from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TaxSource {
+    /// A card in the market
+    Market(Row, Column),
+
+    /// Another player's stash. Only players with a court card from a region
+    /// the taxer rules can be taxed, and each player keeps a rupee sheltered
+    /// for every economic star in their court.
+    Player(Color),
+}
+
+/// Where to place a single block with the build action
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum BuildTarget {
+    Army(Region),
+    Road(Border),
+}
+
+/// A single piece moved with the move action
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Movement {
+    Army { from: Region, to: Region },
+    Road { from: Border, to: Border },
+    Tribe { from: Region, to: Region },
+    Spy { from: CardId, to: CardId },
+}
+
+/// Where a battle takes place
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BattleSite {
+    Region(Region),
+    Card(CardId),
+}
+
+/// A single enemy piece to remove in a battle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BattleTarget {
+    Army(Coalition),
+    Road(Border, Coalition),
+    Tribe(Color),
+    Spy(Color),
+}
+
+/// Something that happened as the result of an action, in the order it
+/// happened. Frontends can use these to animate or log the game.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GameEvent {
+    CardPurchased {
+        player: Color,
+        receipt: Receipt,
+    },
+    CardPlayed {
+        player: Color,
+        card: CardId,
+        side: Side,
+    },
+    RupeesGained {
+        player: Color,
+        amount: i8,
+    },
+    /// A player was taxed
+    RupeesLost {
+        player: Color,
+        amount: i8,
+    },
+    ArmiesPlaced {
+        region: Region,
+        coalition: Coalition,
+        count: i8,
+    },
+    RoadPlaced {
+        border: Border,
+        coalition: Coalition,
+    },
+    TribesPlaced {
+        player: Color,
+        region: Region,
+        count: i8,
+    },
+    ArmiesRemoved {
+        region: Region,
+        coalition: Coalition,
+        count: i8,
+    },
+    RoadRemoved {
+        border: Border,
+        coalition: Coalition,
+    },
+    TribesRemoved {
+        player: Color,
+        region: Region,
+        count: i8,
+    },
+    ArmyMoved {
+        from: Region,
+        to: Region,
+        coalition: Coalition,
+    },
+    RoadMoved {
+        from: Border,
+        to: Border,
+        coalition: Coalition,
+    },
+    TribeMoved {
+        player: Color,
+        from: Region,
+        to: Region,
+    },
+    SpyMoved {
+        player: Color,
+        from: CardId,
+        to: CardId,
+    },
+    /// A card was discarded from a player's hand or court
+    CardDiscarded {
+        player: Color,
+        card: CardId,
+    },
+    /// A player lost their last tribe in a region, so a political card of
+    /// that region was discarded from their court
+    CardOverthrown {
+        player: Color,
+        card: CardId,
+    },
+    /// A card with leverage left a player's court, and they paid back as
+    /// many rupees as they could
+    LeverageRepaid {
+        player: Color,
+        card: CardId,
+        amount: i8,
+    },
+    SpyPlaced {
+        player: Color,
+        card: CardId,
+    },
+    SpyRemoved {
+        player: Color,
+        card: CardId,
+    },
+    ClimateChanged(Suit),
+    HandsExchanged {
+        player: Color,
+        with: Color,
+    },
+    EffectApplied {
+        player: Color,
+        effect: Effect,
+    },
+    GiftPurchased {
+        player: Color,
+        cost: i8,
+    },
+    BribePaid {
+        player: Color,
+        to: Color,
+        amount: i8,
+    },
+    CardBetrayed {
+        player: Color,
+        card: CardId,
+        owner: Color,
+    },
+    PrizeTaken {
+        player: Color,
+        card: CardId,
+    },
+    /// A player switched coalitions, losing their old patriots, prizes and
+    /// gifts
+    LoyaltyChanged {
+        player: Color,
+        coalition: Coalition,
+    },
+    /// An event or dominance card left the market during cleanup. Any rupees
+    /// on it are returned to the bank.
+    EventDiscarded {
+        card: CardId,
+        rupees: i8,
+    },
+    DominanceCheck {
+        card: CardId,
+    },
+    /// A dominance check ended the game
+    GameOver {
+        winner: Color,
+    },
+    /// A card was dealt from the deck into an empty market slot
+    CardDealt {
+        row: Row,
+        column: Column,
+        card: CardId,
+    },
+    TurnEnded {
+        player: Color,
+        next: Color,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ActionError {
+    #[error("the game is over, and {:?} won", .0.winner)]
+    GameOver(GameOver),
+
+    #[error("waiting on {:?} to make a decision", .0.player)]
+    WaitingOn(Request),
+
+    #[error("no actions left this turn")]
+    NoActionsLeft,
+
+    #[error("a {0:?} action has to do something")]
+    NothingToDo(CardAction),
+
+    #[error(transparent)]
+    Purchase(#[from] PurchaseError),
+
+    #[error("there's no card in the {row:?} row, column {column:?}")]
+    EmptySlot { row: Row, column: Column },
+
+    #[error("buying {} needs a choice", .0.name())]
+    ChoiceRequired(CardId),
+
+    #[error("{choice:?} isn't a valid choice for {}", .card.name())]
+    InvalidChoice { card: CardId, choice: EventChoice },
+
+    #[error("{0:?} can't be targeted")]
+    InvalidTarget(Color),
+
+    #[error("{} isn't in your hand", .0.name())]
+    NotInHand(CardId),
+
+    #[error("{} isn't in your court", .0.name())]
+    NotInCourt(CardId),
+
+    #[error("{} isn't in any court", .0.name())]
+    NotInAnyCourt(CardId),
+
+    #[error("{} has already been used this turn", .0.name())]
+    Tapped(CardId),
+
+    #[error("{} doesn't have the {action:?} action", .card.name())]
+    MissingAction { card: CardId, action: CardAction },
+
+    #[error("costs {cost} rupees, but only {available} are available")]
+    CannotAfford { cost: i8, available: i8 },
+
+    #[error("expected {expected} {piece}, got {found}")]
+    WrongCount {
+        piece: &'static str,
+        expected: usize,
+        found: usize,
+    },
+
+    #[error("there's no card at index {index} in your {pile}")]
+    InvalidDiscard { pile: &'static str, index: usize },
+
+    #[error("{border:?} isn't a border of {region:?}")]
+    InvalidRoad { border: Border, region: Region },
+
+    #[error(transparent)]
+    Border(#[from] BorderError),
+
+    #[error("can't make {requested} moves with a rank {rank} card")]
+    TooManyMoves { requested: usize, rank: i8 },
+
+    #[error("there's no {coalition:?} road on {border:?}")]
+    NoRoad {
+        border: Border,
+        coalition: Coalition,
+    },
+
+    #[error("roads can only move between borders of the same region, not {from:?} and {to:?}")]
+    InvalidRoadMove { from: Border, to: Border },
+
+    #[error("a spy can't move from {} to {} in one step", .from.name(), .to.name())]
+    InvalidSpyMove { from: CardId, to: CardId },
+
+    #[error("tribes can only move with Nationalism")]
+    TribesCannotMove,
+
+    #[error("nothing to move for {0:?}")]
+    NothingToMove(Movement),
+
+    #[error("spies can only be placed on cards in {region:?}, not {}", .card.name())]
+    InvalidSpy { card: CardId, region: Region },
+
+    #[error("can only remove {max} pieces in this battle, not {requested}")]
+    TooManyTargets { requested: usize, max: usize },
+
+    #[error("{0:?} isn't an enemy")]
+    NotAnEnemy(BattleTarget),
+
+    #[error("there aren't enough {0:?} pieces to remove")]
+    NoTarget(BattleTarget),
+
+    #[error("{target:?} is protected by {}", .card.name())]
+    ProtectedTarget { target: BattleTarget, card: CardId },
+
+    #[error("you don't rule {0:?}")]
+    NotRuled(Region),
+
+    #[error("you don't rule either side of {0:?}")]
+    NotRuledBorder(Border),
+
+    #[error("can't build {requested} blocks, only {max}")]
+    TooManyBlocks { requested: usize, max: usize },
+
+    #[error("can't take {requested} {coalition:?} blocks from a supply of {available}")]
+    NotEnoughBlocks {
+        coalition: Coalition,
+        requested: usize,
+        available: i8,
+    },
+
+    #[error("can't take {requested} rupees in taxes with a rank {rank} card")]
+    TaxTooHigh { requested: usize, rank: i8 },
+
+    #[error("you don't rule a region matching any of {0:?}'s court cards")]
+    CannotTax(Color),
+
+    #[error("can't take {requested} rupees from {from:?}, which only has {available}")]
+    NotEnoughRupees {
+        from: TaxSource,
+        requested: i8,
+        available: i8,
+    },
+
+    #[error("can't buy more than {MAX_GIFTS} gifts")]
+    TooManyGifts,
+
+    #[error("no cylinders left in your bank")]
+    NoCylinders,
+
+    #[error("you don't have a spy on {}", .0.name())]
+    NoSpy(CardId),
+
+    #[error("{} is protected by bodyguards", .0.name())]
+    Protected(CardId),
+
+    #[error("{} has no prize", .0.name())]
+    NoPrize(CardId),
+}
+
+/// How a court card's action will be paid for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct CardUse {
+    /// A bonus action doesn't count against the player's actions for the turn
+    bonus: bool,
+
+    /// The bribe owed to the player holding the card hostage
+    bribe: Option<Bribe>,
+}
+
+impl CardUse {
+    pub(super) fn bribe_amount(&self) -> i8 {
+        self.bribe.map_or(0, |bribe| bribe.amount)
+    }
+}
+
+/// The number of pieces placed by playing a card
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct PlayCounts {
+    pub roads: i8,
+    pub tribes: i8,
+    pub spies: i8,
+}
+
+/// Check that the right number of pieces were placed
+fn check_count(piece: &'static str, expected: i8, found: usize) -> Result<(), ActionError> {
+    let expected = expected.max(0) as usize;
+
+    match expected == found {
+        true => Ok(()),
+        false => Err(ActionError::WrongCount {
+            piece,
+            expected,
+            found,
+        }),
+    }
+}
+
+pub(super) fn check_funds(player: &PlayerState, cost: i8) -> Result<(), ActionError> {
+    let available = player.rupees.count();
+
+    match cost <= available {
+        true => Ok(()),
+        false => Err(ActionError::CannotAfford { cost, available }),
+    }
+}
+
+impl Game {
+    /// Validate an action for the current player and, if it's legal, apply
+    /// it. If the action is rejected, the game is left untouched. On success,
+    /// returns everything that happened, in order.
+    pub fn apply(&mut self, action: Action) -> Result<Vec<GameEvent>, ActionError> {
+        self.check_not_over().map_err(ActionError::GameOver)?;
+        self.check_no_requests().map_err(ActionError::WaitingOn)?;
+
+        match action {
+            Action::Purchase {
+                row,
+                column,
+                choice,
+            } => self.purchase(row, column, choice),
+            Action::Play {
+                card,
+                side,
+                roads,
+                spies,
+            } => self.play(card, side, &roads, &spies),
+            Action::Tax { card, sources } => self.tax(card, &sources),
+            Action::Gift { card } => self.gift(card),
+            Action::Betray {
+                card,
+                target,
+                take_prize,
+            } => self.betray(card, target, take_prize),
+            Action::Move { card, moves } => self.move_pieces(card, &moves),
+            Action::Build { card, targets } => self.build(card, &targets),
+            Action::Battle {
+                card,
+                site,
+                targets,
+            } => self.battle(card, site, &targets),
+            Action::EndTurn(discards) => self.end_turn(discards),
+        }
+    }
+
+    /// Check whether an action is legal for the current player, without
+    /// applying it
+    pub fn validate(&self, action: &Action) -> Result<(), ActionError> {
+        self.check_not_over().map_err(ActionError::GameOver)?;
+        self.check_no_requests().map_err(ActionError::WaitingOn)?;
+
+        match *action {
+            Action::Purchase {
+                row,
+                column,
+                choice,
+            } => self.check_purchase(row, column, choice),
+            Action::Play {
+                card,
+                ref roads,
+                ref spies,
+                ..
+            } => self.check_play(card, roads, spies).map(drop),
+            Action::Tax { card, ref sources } => self.check_tax(card, sources).map(drop),
+            Action::Gift { card } => self.check_gift(card).map(drop),
+            Action::Build { card, ref targets } => self.check_build(card, targets).map(drop),
+            Action::Move { card, ref moves } => self.check_move(card, moves).map(drop),
+            Action::Betray {
+                card,
+                target,
+                take_prize,
+            } => self.check_betray(card, target, take_prize).map(drop),
+            Action::Battle {
+                card,
+                site,
+                ref targets,
+            } => self.check_battle(card, site, targets).map(drop),
+            Action::EndTurn(ref discards) => self.check_end_turn(discards),
+        }
+    }
+
+    pub(super) fn check_actions_left(&self) -> Result<(), ActionError> {
+        match self.turn.actions_taken < ACTIONS_PER_TURN {
+            true => Ok(()),
+            false => Err(ActionError::NoActionsLeft),
+        }
+    }
+
+    /// Court cards matching the favored suit can be used without spending an
+    /// action
+    fn is_favored(&self, player: &PlayerState, card: &CourtCard) -> bool {
+        card.suit == self.climate
+            || (card.suit == Suit::Military && player.effects.new_tactics)
+            || matches!(
+                card.ability,
+                Some(SpecialAbility::SavvyOperator | SpecialAbility::Irregulars)
+            )
+    }
+
+    /// Check that the current player can use an action on one of their court
+    /// cards, and can pay any bribe it needs
+    pub(super) fn check_card_action(
+        &self,
+        card: CardId,
+        action: CardAction,
+    ) -> Result<CardUse, ActionError> {
+        let player = self.current_player();
+        let court_card = player
+            .state
+            .court
+            .get(card)
+            .ok_or(ActionError::NotInCourt(card))?;
+
+        if court_card.tapped {
+            return Err(ActionError::Tapped(card));
+        }
+
+        if !court_card.actions.has(action) {
+            return Err(ActionError::MissingAction { card, action });
+        }
+
+        let bonus = self.is_favored(&player.state, court_card);
+
+        if !bonus {
+            self.check_actions_left()?;
+        }
+
+        let card_use = CardUse {
+            bonus,
+            bribe: self.pricing().hostage_bribe(player.color, court_card),
+        };
+
+        check_funds(&player.state, card_use.bribe_amount())?;
+
+        Ok(card_use)
+    }
+
+    /// Tap a court card after using one of its actions, and pay any bribe
+    pub(super) fn use_card(
+        &mut self,
+        card: CardId,
+        card_use: CardUse,
+        events: &mut Vec<GameEvent>,
+    ) {
+        let player = self.current_player_mut();
+        let color = player.color;
+
+        if let Some(court_card) = player.state.court.get_mut(card) {
+            court_card.tapped = true;
+        }
+
+        self.pay_bribe(color, card_use.bribe, events);
+
+        if !card_use.bonus {
+            self.turn.actions_taken += 1;
+        }
+    }
+
+    /// Pay a bribe from one player to another, if there is one
+    fn pay_bribe(&mut self, player: Color, bribe: Option<Bribe>, events: &mut Vec<GameEvent>) {
+        if let Some(Bribe { to, amount }) = bribe {
+            let rupees = self.players[player].state.rupees.take_up_to(amount);
+            self.players[to].state.rupees.add(rupees);
+
+            events.push(GameEvent::BribePaid { player, to, amount });
+        }
+    }
+
+    /// Check that the current player can buy the card in a market slot, with
+    /// the choice its purchase effect needs, if any
+    fn check_purchase(
+        &self,
+        row: Row,
+        column: Column,
+        choice: Option<EventChoice>,
+    ) -> Result<(), ActionError> {
+        self.check_actions_left()?;
+
+        self.market.check_purchase(
+            row,
+            column,
+            self.pricing().purchase_cost(column),
+            &self.current_player().state.rupees,
+        )?;
+
+        let card = self
+            .market
+            .get_card(row, column)
+            .ok_or(ActionError::EmptySlot { row, column })?;
+
+        let purchase_event = match **card {
+            cards::Card::Event(event::Card::Event(ref event)) => Some(event.purchase),
+            _ => None,
+        };
+
+        match (purchase_event, choice) {
+            (Some(purchase), Some(choice)) if choice.is_for(purchase) => {
+                self.check_event_choice(choice)
+            }
+            (Some(purchase), None) if purchase.needs_choice() => {
+                Err(ActionError::ChoiceRequired(card.id()))
+            }
+            (_, Some(choice)) => Err(ActionError::InvalidChoice {
+                card: card.id(),
+                choice,
+            }),
+            (_, None) => Ok(()),
+        }
+    }
+
+    /// Check that an event's target is another player in the game
+    fn check_event_choice(&self, choice: EventChoice) -> Result<(), ActionError> {
+        match choice {
+            EventChoice::Rumor(target) | EventChoice::OtherPersuasiveMethods(target) => {
+                match target != self.current_player().color
+                    && self.players.iter().any(|player| player.color == target)
+                {
+                    true => Ok(()),
+                    false => Err(ActionError::InvalidTarget(target)),
+                }
+            }
+            EventChoice::PashtunwaliValues(_) | EventChoice::Rebuke(_) => Ok(()),
+        }
+    }
+
+    fn purchase(
+        &mut self,
+        row: Row,
+        column: Column,
+        choice: Option<EventChoice>,
+    ) -> Result<Vec<GameEvent>, ActionError> {
+        self.check_purchase(row, column, choice)?;
+
+        let cost = self.pricing().purchase_cost(column);
+        let player = &mut self.players[self.turn.player];
+        let color = player.color;
+
+        let (card_taken, receipt) =
+            self.market
+                .purchase(row, column, cost, &mut player.state.rupees)?;
+
+        let mut events = vec![GameEvent::CardPurchased {
+            player: color,
+            receipt,
+        }];
+
+        self.turn.actions_taken += 1;
+
+        match card_taken {
+            cards::Card::Court(card) => player.state.hand.cards.push(card),
+            cards::Card::Event(event::Card::Event(event)) => {
+                self.apply_purchase_event(event.purchase, choice, &mut events);
+                self.discard
+                    .push(cards::Card::Event(event::Card::Event(event)));
+            }
+            cards::Card::Event(event::Card::Dominance(id)) => {
+                self.dominance_check(id, &mut events);
+            }
+        }
+
+        Ok(events)
+    }
+
+    /// The number of roads, tribes and spies the current player would place
+    /// by playing a card. Only as many pieces as there are in the supply are
+    /// placed. Tribes are placed before spies, and both come from the
+    /// player's bank.
+    pub(super) fn play_counts(&self, card: &court::Card) -> PlayCounts {
+        let player = &self.current_player().state;
+        let impact = card.impact;
+        let tribes = cmp::min(impact.tribes, player.bank.count());
+
+        PlayCounts {
+            roads: cmp::min(impact.roads, self.blocks.count(player.loyalty)),
+            tribes,
+            spies: cmp::min(impact.spies, player.bank.count() - tribes),
+        }
+    }
+
+    /// Check that the current player can play a card from their hand with the
+    /// given placements. Returns the card's position in the hand.
+    fn check_play(
+        &self,
+        card: CardId,
+        roads: &[Border],
+        spies: &[CardId],
+    ) -> Result<usize, ActionError> {
+        self.check_actions_left()?;
+
+        let player = &self.current_player().state;
+        let index = player
+            .hand
+            .position(card)
+            .ok_or(ActionError::NotInHand(card))?;
+
+        let data = &player.hand.cards[index];
+        let region = data.region;
+        let counts = self.play_counts(data);
+
+        let bribe = self.pricing().play_bribe(self.current_player().color, data);
+        check_funds(player, bribe.map_or(0, |bribe| bribe.amount))?;
+
+        check_count("roads", counts.roads, roads.len())?;
+        check_count("spies", counts.spies, spies.len())?;
+
+        if let Some(&border) = roads
+            .iter()
+            .find(|&&border| !border.borders(region) || !self.map.has_border(border))
+        {
+            return Err(ActionError::InvalidRoad { border, region });
+        }
+
+        // Spies go on any court card in the same region, including the card
+        // being played
+        let in_region = |target: CardId| {
+            target == card
+                || self
+                    .court_card(target)
+                    .is_some_and(|target| target.region == region)
+        };
+
+        if let Some(&target) = spies.iter().find(|&&target| !in_region(target)) {
+            return Err(ActionError::InvalidSpy {
+                card: target,
+                region,
+            });
+        }
+
+        Ok(index)
+    }
+
+    fn play(
+        &mut self,
+        card: CardId,
+        side: Side,
+        roads: &[Border],
+        spies: &[CardId],
+    ) -> Result<Vec<GameEvent>, ActionError> {
+        let index = self.check_play(card, roads, spies)?;
+
+        let player = self.current_player();
+        let color = player.color;
+        let loyalty = player.state.loyalty;
+        let data = &player.state.hand.cards[index];
+        let region = data.region;
+        let impact = data.impact;
+        let tribe_count = self.play_counts(data).tribes;
+        let bribe = self.pricing().play_bribe(color, data);
+
+        let state = &mut self.players[self.turn.player].state;
+        let played = state.hand.cards.remove(index);
+        state.court.play(CourtCard::new(played), side);
+        self.turn.actions_taken += 1;
+
+        let mut events = Vec::new();
+        self.pay_bribe(color, bribe, &mut events);
+        events.push(GameEvent::CardPlayed {
+            player: color,
+            card,
+            side,
+        });
+
+        if impact.armies > 0 {
+            let armies = self.blocks.take_up_to(impact.armies, loyalty);
+            let count = armies.count(loyalty);
+            self.map.add_armies(region, armies);
+
+            if count > 0 {
+                events.push(GameEvent::ArmiesPlaced {
+                    region,
+                    coalition: loyalty,
+                    count,
+                });
+            }
+        }
+
+        for &border in roads {
+            self.map
+                .add_roads(border, self.blocks.take_up_to(1, loyalty))
+                .expect("roads were checked");
+            events.push(GameEvent::RoadPlaced {
+                border,
+                coalition: loyalty,
+            });
+        }
+
+        if tribe_count > 0 {
+            for _ in 0..tribe_count {
+                self.place_tribe(color, region)
+                    .expect("tribes are limited to the player's bank");
+            }
+            events.push(GameEvent::TribesPlaced {
+                player: color,
+                region,
+                count: tribe_count,
+            });
+        }
+
+        for &target in spies {
+            let spy = self.players[color].state.bank.take_up_to(1);
+            let owner = self.court_owner(target).expect("spy target was checked");
+
+            self.players[owner]
+                .state
+                .court
+                .get_mut(target)
+                .expect("spy target was checked")
+                .spies
+                .add(color, spy);
+
+            events.push(GameEvent::SpyPlaced {
+                player: color,
+                card: target,
+            });
+        }
+
+        if impact.leverage {
+            self.players[color]
+                .state
+                .rupees
+                .add(RupeeSet::new(LEVERAGE));
+            events.push(GameEvent::RupeesGained {
+                player: color,
+                amount: LEVERAGE,
+            });
+        }
+
+        if let Some(suit) = impact.favor {
+            let climate = self.climate;
+            self.try_set_climate(suit);
+
+            if self.climate != climate {
+                events.push(GameEvent::ClimateChanged(suit));
+            }
+        }
+
+        Ok(events)
+    }
+
+    /// Check that the current player can tax with a court card. Returns
+    /// whether it's a bonus action, along with the number of rupees taken
+    /// from each source.
+    fn check_tax(
+        &self,
+        card: CardId,
+        sources: &[TaxSource],
+    ) -> Result<(CardUse, HashMap<TaxSource, i8>), ActionError> {
+        let card_use = self.check_card_action(card, CardAction::Tax)?;
+        if sources.is_empty() {
+            return Err(ActionError::NothingToDo(CardAction::Tax));
+        }
+        let rank = self
+            .current_player()
+            .state
+            .court
+            .get(card)
+            .map_or(0, |card| card.rank.value());
+
+        if sources.len() > rank as usize {
+            return Err(ActionError::TaxTooHigh {
+                requested: sources.len(),
+                rank,
+            });
+        }
+
+        // Tally up how much is taken from each source
+        let mut taken: HashMap<TaxSource, i8> = HashMap::new();
+        sources
+            .iter()
+            .for_each(|&source| *taken.entry(source).or_default() += 1);
+
+        for (&source, &requested) in &taken {
+            let available = match source {
+                TaxSource::Market(row, column) => self
+                    .market
+                    .get_card(row, column)
+                    .ok_or(ActionError::EmptySlot { row, column })?
+                    .rupees(),
+                TaxSource::Player(target) => {
+                    self.check_taxable(target)?;
+                    self.pricing().taxable_rupees(&self.players[target].state)
+                }
+            };
+
+            if requested > available {
+                return Err(ActionError::NotEnoughRupees {
+                    from: source,
+                    requested,
+                    available,
+                });
+            }
+        }
+
+        Ok((card_use, taken))
+    }
+
+    /// Check that the current player can tax another player. They have to
+    /// rule a region matching one of that player's court cards, unless Claim
+    /// of Ancient Lineage lets them tax as though they rule everywhere.
+    fn check_taxable(&self, target: Color) -> Result<(), ActionError> {
+        let player = self.current_player();
+
+        if target == player.color || !self.players.iter().any(|other| other.color == target) {
+            return Err(ActionError::InvalidTarget(target));
+        }
+
+        let claim = player
+            .state
+            .court
+            .cards
+            .iter()
+            .any(|card| card.ability == Some(SpecialAbility::ClaimOfAncientLineage));
+
+        let taxable =
+            self.players[target].state.court.cards.iter().any(|card| {
+                claim || self.map.ruler(card.region, &self.players) == Some(player.color)
+            });
+
+        match taxable {
+            true => Ok(()),
+            false => Err(ActionError::CannotTax(target)),
+        }
+    }
+
+    fn tax(&mut self, card: CardId, sources: &[TaxSource]) -> Result<Vec<GameEvent>, ActionError> {
+        let (card_use, taken) = self.check_tax(card, sources)?;
+
+        let mut events = Vec::new();
+        self.use_card(card, card_use, &mut events);
+
+        let mut total = RupeeSet::empty();
+
+        // Go through the sources in the order they were given, so the events
+        // come out in that order too
+        for source in sources.iter().unique() {
+            let count = taken[source];
+
+            match *source {
+                TaxSource::Market(row, column) => {
+                    let market_card = self
+                        .market
+                        .get_row_mut(row)
+                        .get_card_mut(column)
+                        .expect("tax source was checked");
+
+                    total.add(market_card.take_rupees(count));
+                }
+                TaxSource::Player(target) => {
+                    total.add(self.players[target].state.rupees.take_up_to(count));
+                    events.push(GameEvent::RupeesLost {
+                        player: target,
+                        amount: count,
+                    });
+                }
+            }
+        }
+
+        let color = self.current_player().color;
+        let amount = total.count();
+        self.current_player_mut().state.rupees.add(total);
+
+        events.push(GameEvent::RupeesGained {
+            player: color,
+            amount,
+        });
+
+        Ok(events)
+    }
+
+    /// Check that the current player can buy a gift with a court card.
+    /// Returns the gift's cost.
+    fn check_gift(&self, card: CardId) -> Result<(CardUse, i8), ActionError> {
+        let card_use = self.check_card_action(card, CardAction::Gift)?;
+
+        let player = &self.current_player().state;
+        let cost = self
+            .pricing()
+            .gift_cost(player)
+            .ok_or(ActionError::TooManyGifts)?;
+
+        check_funds(player, cost + card_use.bribe_amount())?;
+
+        if player.bank.count() == 0 {
+            return Err(ActionError::NoCylinders);
+        }
+
+        Ok((card_use, cost))
+    }
+
+    fn gift(&mut self, card: CardId) -> Result<Vec<GameEvent>, ActionError> {
+        let (card_use, cost) = self.check_gift(card)?;
+
+        let mut events = Vec::new();
+        self.use_card(card, card_use, &mut events);
+
+        let color = self.current_player().color;
+        let state = &mut self.current_player_mut().state;
+        let payment = state.rupees.take_up_to(cost);
+        let gift = state.bank.take_up_to(1);
+        state.gifts.add(gift);
+
+        self.market.spend_for_ability(payment);
+
+        events.push(GameEvent::GiftPurchased {
+            player: color,
+            cost,
+        });
+
+        Ok(events)
+    }
+
+    /// Check that the current player can betray a card. Returns the owner of
+    /// the betrayed card.
+    fn check_betray(
+        &self,
+        card: CardId,
+        target: CardId,
+        take_prize: bool,
+    ) -> Result<(CardUse, Color), ActionError> {
+        let card_use = self.check_card_action(card, CardAction::Betray)?;
+
+        let player = &self.current_player().state;
+        let color = self.current_player().color;
+        check_funds(
+            player,
+            self.pricing().betray_cost() + card_use.bribe_amount(),
+        )?;
+
+        let owner = self
+            .court_owner(target)
+            .ok_or(ActionError::NotInAnyCourt(target))?;
+        let owner_court = &self.players[owner].state.court;
+        let target_card = owner_court.get(target).expect("owner was just found");
+
+        if target_card.spies.count(color) == 0 {
+            return Err(ActionError::NoSpy(target));
+        }
+
+        if target_card.suit == Suit::Political
+            && owner_court
+                .cards
+                .iter()
+                .any(|card| card.ability == Some(SpecialAbility::Bodyguards))
+        {
+            return Err(ActionError::Protected(target));
+        }
+
+        if take_prize && target_card.prize.is_none() {
+            return Err(ActionError::NoPrize(target));
+        }
+
+        Ok((card_use, owner))
+    }
+
+    fn betray(
+        &mut self,
+        card: CardId,
+        target: CardId,
+        take_prize: bool,
+    ) -> Result<Vec<GameEvent>, ActionError> {
+        let (card_use, owner) = self.check_betray(card, target, take_prize)?;
+        let color = self.current_player().color;
+        let cost = self.pricing().betray_cost();
+
+        // Tap first, in case the card betrays itself
+        let mut events = Vec::new();
+        self.use_card(card, card_use, &mut events);
+
+        let payment = self.current_player_mut().state.rupees.take_up_to(cost);
+        self.market.spend_for_ability(payment);
+
+        let betrayed = self
+            .remove_court_card(target)
+            .expect("target was checked to be in a court");
+
+        events.push(GameEvent::CardBetrayed {
+            player: color,
+            card: target,
+            owner,
+        });
+        self.court_card_lost(owner, &betrayed, &mut events);
+
+        match take_prize {
+            true => {
+                // Taking a prize of another coalition changes loyalty first,
+                // so the new prize isn't lost along with the old ones
+                let prize = betrayed.prize.expect("prize was checked");
+                if prize != self.current_player().state.loyalty {
+                    self.change_loyalty(color, prize, &mut events);
+                }
+
+                self.current_player_mut().state.prizes.cards.push(betrayed);
+                events.push(GameEvent::PrizeTaken {
+                    player: color,
+                    card: target,
+                });
+            }
+            false => self.discard.push(cards::Card::Court(betrayed)),
+        }
+
+        Ok(events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        deck::Deck,
+        game::test_util::{self, court_card, id, in_court, red, red_mut},
+        map::{Kabul, Kandahar, Punjab},
+        market::{Market, Payment},
+    };
+
+    /// A two player game where it's red's turn, with a market of court cards
+    /// that don't appear anywhere else in the tests
+    fn new_game() -> Game {
+        let mut game = test_util::new_game();
+        let market_cards = (30..42).map(|number| CardId::new(number).unwrap());
+
+        game.market = Market::new();
+        game.market
+            .fill_from(&mut Deck::from_ids(market_cards).unwrap().draws());
+        game.climate = Suit::Political;
+        game
+    }
+
+    #[test]
+    fn test_purchase() {
+        let mut game = new_game();
+        let bought = game.market.get_card(Row::Top, Column::Two).unwrap().id();
+
+        let events = game
+            .apply(Action::Purchase {
+                row: Row::Top,
+                column: Column::Two,
+                choice: None,
+            })
+            .unwrap();
+
+        assert_eq!(
+            events,
+            [GameEvent::CardPurchased {
+                player: Color::Red,
+                receipt: Receipt {
+                    card: bought,
+                    cost: 2,
+                    payments: vec![
+                        Payment::Card {
+                            row: Row::Top,
+                            column: Column::Zero
+                        },
+                        Payment::Card {
+                            row: Row::Top,
+                            column: Column::One
+                        },
+                    ],
+                    collected: 0,
+                },
+            }]
+        );
+
+        assert_eq!(red(&game).rupees.count(), 2);
+        assert_eq!(red(&game).hand.position(bought), Some(0));
+        assert_eq!(game.turn.actions_taken, 1);
+
+        for column in [Column::Zero, Column::One] {
+            let card = game.market.get_card(Row::Top, column).unwrap();
+            assert_eq!(card.rupees(), 1);
+            assert!(card.is_marked());
+        }
+
+        assert!(game.market.get_card(Row::Top, Column::Two).is_none());
+        assert_eq!(
+            game.market
+                .get_card(Row::Bottom, Column::Zero)
+                .unwrap()
+                .rupees(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_rejected_purchase_changes_nothing() {
+        let mut game = new_game();
+        let action = Action::Purchase {
+            row: Row::Bottom,
+            column: Column::Five,
+            choice: None,
+        };
+
+        assert_eq!(
+            game.apply(action.clone()),
+            Err(ActionError::Purchase(PurchaseError::CannotAfford {
+                cost: 5,
+                available: 4
+            }))
+        );
+
+        assert_eq!(red(&game).rupees.count(), 4);
+        assert!(red(&game).hand.cards.is_empty());
+        assert!(game.market.get_card(Row::Bottom, Column::Five).is_some());
+        assert_eq!(game.turn.actions_taken, 0);
+
+        game.turn.actions_taken = ACTIONS_PER_TURN;
+        red_mut(&mut game).rupees.add(RupeeSet::new(10));
+        assert_eq!(game.apply(action), Err(ActionError::NoActionsLeft));
+    }
+
+    #[test]
+    fn test_play() {
+        let mut game = new_game();
+        red_mut(&mut game)
+            .hand
+            .cards
+            .push(court_card("Money Lenders"));
+        red_mut(&mut game)
+            .court
+            .cards
+            .push_back(in_court("Dost Mohammad"));
+
+        let lenders = id("Money Lenders");
+        let roads = vec![Border::new(Kabul, Punjab), Border::new(Kabul, Kandahar)];
+
+        let events = game
+            .apply(Action::Play {
+                card: lenders,
+                side: Side::Left,
+                roads: roads.clone(),
+                spies: vec![],
+            })
+            .unwrap();
+
+        assert_eq!(
+            events,
+            [
+                GameEvent::CardPlayed {
+                    player: Color::Red,
+                    card: lenders,
+                    side: Side::Left,
+                },
+                GameEvent::RoadPlaced {
+                    border: roads[0],
+                    coalition: Coalition::Britain,
+                },
+                GameEvent::RoadPlaced {
+                    border: roads[1],
+                    coalition: Coalition::Britain,
+                },
+                GameEvent::RupeesGained {
+                    player: Color::Red,
+                    amount: LEVERAGE,
+                },
+            ]
+        );
+
+        assert_eq!(red(&game).court.position(lenders), Some(0));
+        assert!(red(&game).hand.cards.is_empty());
+        assert_eq!(red(&game).rupees.count(), 6);
+        assert_eq!(game.map.total_block_counts()[Coalition::Britain], 2);
+        assert_eq!(game.blocks.count(Coalition::Britain), 10);
+    }
+
+    #[test]
+    fn test_play_rejects_bad_placements() {
+        let mut game = new_game();
+        red_mut(&mut game)
+            .hand
+            .cards
+            .push(court_card("Money Lenders"));
+
+        let play = |roads| Action::Play {
+            card: id("Money Lenders"),
+            side: Side::Right,
+            roads,
+            spies: vec![],
+        };
+
+        assert_eq!(
+            game.apply(play(vec![Border::new(Kabul, Punjab)])),
+            Err(ActionError::WrongCount {
+                piece: "roads",
+                expected: 2,
+                found: 1
+            })
+        );
+
+        let bad_road = Border::new(Kandahar, Punjab);
+        assert_eq!(
+            game.apply(play(vec![Border::new(Kabul, Punjab), bad_road])),
+            Err(ActionError::InvalidRoad {
+                border: bad_road,
+                region: Kabul
+            })
+        );
+
+        assert_eq!(red(&game).hand.cards.len(), 1);
+        assert_eq!(game.map.total_block_counts()[Coalition::Britain], 0);
+        assert_eq!(game.turn.actions_taken, 0);
+    }
+
+    #[test]
+    fn test_play_spies_and_tribes() {
+        let mut game = new_game();
+        red_mut(&mut game)
+            .hand
+            .cards
+            .push(court_card("Alexander Burnes"));
+        red_mut(&mut game)
+            .hand
+            .cards
+            .push(court_card("Dost Mohammad"));
+
+        let burnes = id("Alexander Burnes");
+        game.apply(Action::Play {
+            card: burnes,
+            side: Side::Right,
+            roads: vec![],
+            spies: vec![burnes, burnes],
+        })
+        .unwrap();
+
+        assert_eq!(
+            red(&game)
+                .court
+                .get(burnes)
+                .unwrap()
+                .spies
+                .count(Color::Red),
+            2
+        );
+        assert_eq!(red(&game).bank.count(), 8);
+
+        game.apply(Action::Play {
+            card: id("Dost Mohammad"),
+            side: Side::Right,
+            roads: vec![],
+            spies: vec![],
+        })
+        .unwrap();
+
+        assert_eq!(game.map.total_tribe_counts()[Color::Red], 2);
+        assert_eq!(red(&game).bank.count(), 6);
+    }
+
+    #[test]
+    fn test_favored_card_actions_are_free() {
+        let mut game = new_game();
+        let dost = id("Dost Mohammad");
+        red_mut(&mut game)
+            .court
+            .cards
+            .push_back(in_court("Dost Mohammad"));
+
+        let events = game.apply(Action::Gift { card: dost }).unwrap();
+
+        assert_eq!(
+            events,
+            [GameEvent::GiftPurchased {
+                player: Color::Red,
+                cost: 2
+            }]
+        );
+        assert_eq!(red(&game).gifts.count(), 1);
+        assert_eq!(red(&game).rupees.count(), 2);
+        assert_eq!(game.turn.actions_taken, 0);
+
+        // The payment went into the market, starting from the back
+        assert_eq!(
+            game.market
+                .get_card(Row::Top, Column::Five)
+                .unwrap()
+                .rupees(),
+            1
+        );
+        assert_eq!(
+            game.market
+                .get_card(Row::Bottom, Column::Five)
+                .unwrap()
+                .rupees(),
+            1
+        );
+
+        assert_eq!(
+            game.apply(Action::Gift { card: dost }),
+            Err(ActionError::Tapped(dost))
+        );
+    }
+
+    #[test]
+    fn test_tax_market() {
+        let mut game = new_game();
+        let burnes = id("Alexander Burnes");
+        red_mut(&mut game)
+            .court
+            .cards
+            .push_back(in_court("Alexander Burnes"));
+        game.market
+            .get_row_mut(Row::Top)
+            .get_card_mut(Column::One)
+            .unwrap()
+            .add_rupees(RupeeSet::new(2));
+
+        let source = TaxSource::Market(Row::Top, Column::One);
+
+        assert_eq!(
+            game.apply(Action::Tax {
+                card: burnes,
+                sources: vec![source; 3],
+            }),
+            Err(ActionError::NotEnoughRupees {
+                from: source,
+                requested: 3,
+                available: 2
+            })
+        );
+
+        game.apply(Action::Tax {
+            card: burnes,
+            sources: vec![source; 2],
+        })
+        .unwrap();
+
+        assert_eq!(red(&game).rupees.count(), 6);
+        assert_eq!(
+            game.market
+                .get_card(Row::Top, Column::One)
+                .unwrap()
+                .rupees(),
+            0
+        );
+        assert_eq!(game.turn.actions_taken, 1);
+        assert!(red(&game).court.get(burnes).unwrap().tapped);
+    }
+
+    #[test]
+    fn test_tax_players() {
+        let mut game = new_game();
+        let burnes = id("Alexander Burnes");
+        let tax = |sources: Vec<TaxSource>| Action::Tax {
+            card: burnes,
+            sources,
+        };
+
+        red_mut(&mut game)
+            .court
+            .cards
+            .push_back(in_court("Alexander Burnes"));
+
+        // Kabul Bazaar shelters one of blue's three rupees
+        let blue = &mut game.players[Color::Blue].state;
+        blue.court.cards.push_back(in_court("Kabul Bazaar"));
+        blue.rupees.take_up_to(1);
+
+        let blue_tax = TaxSource::Player(Color::Blue);
+        assert_eq!(
+            game.validate(&tax(vec![blue_tax])),
+            Err(ActionError::CannotTax(Color::Blue))
+        );
+        assert_eq!(
+            game.validate(&tax(vec![TaxSource::Player(Color::Red)])),
+            Err(ActionError::InvalidTarget(Color::Red))
+        );
+
+        // Ruling Kabul lets red tax anyone with a Kabul card
+        game.place_tribe(Color::Red, Region::Kabul).unwrap();
+        assert_eq!(
+            game.validate(&tax(vec![blue_tax; 3])),
+            Err(ActionError::NotEnoughRupees {
+                from: blue_tax,
+                requested: 3,
+                available: 2,
+            })
+        );
+
+        let events = game.apply(tax(vec![blue_tax; 2])).unwrap();
+        assert_eq!(
+            events,
+            [
+                GameEvent::RupeesLost {
+                    player: Color::Blue,
+                    amount: 2,
+                },
+                GameEvent::RupeesGained {
+                    player: Color::Red,
+                    amount: 2,
+                },
+            ]
+        );
+        assert_eq!(red(&game).rupees.count(), 6);
+        assert_eq!(game.players[Color::Blue].state.rupees.count(), 1);
+    }
+
+    #[test]
+    fn test_claim_of_ancient_lineage_taxes_anyone() {
+        let mut game = new_game();
+        red_mut(&mut game)
+            .court
+            .cards
+            .push_back(in_court("Shah Shujah Durrani"));
+        game.players[Color::Blue]
+            .state
+            .court
+            .cards
+            .push_back(in_court("Mir Masjidi"));
+
+        let events = game
+            .apply(Action::Tax {
+                card: id("Shah Shujah Durrani"),
+                sources: vec![TaxSource::Player(Color::Blue); 2],
+            })
+            .unwrap();
+
+        assert_eq!(
+            events.last(),
+            Some(&GameEvent::RupeesGained {
+                player: Color::Red,
+                amount: 2,
+            })
+        );
+    }
+
+    #[test]
+    fn test_betray_for_prize() {
+        let mut game = new_game();
+        let burnes = id("Alexander Burnes");
+        let masson = id("Charles Masson");
+
+        red_mut(&mut game)
+            .court
+            .cards
+            .push_back(in_court("Alexander Burnes"));
+
+        let mut target = in_court("Charles Masson");
+        target
+            .spies
+            .add(Color::Red, red_mut(&mut game).bank.take_up_to(1));
+        target.spies.add(
+            Color::Blue,
+            game.players[Color::Blue].state.bank.take_up_to(1),
+        );
+        game.players[Color::Blue]
+            .state
+            .court
+            .cards
+            .push_back(target);
+
+        let events = game
+            .apply(Action::Betray {
+                card: burnes,
+                target: masson,
+                take_prize: true,
+            })
+            .unwrap();
+
+        assert_eq!(
+            events,
+            [
+                GameEvent::CardBetrayed {
+                    player: Color::Red,
+                    card: masson,
+                    owner: Color::Blue,
+                },
+                GameEvent::PrizeTaken {
+                    player: Color::Red,
+                    card: masson,
+                },
+            ]
+        );
+
+        assert_eq!(red(&game).prizes.cards.len(), 1);
+        assert_eq!(red(&game).rupees.count(), 2);
+        assert_eq!(red(&game).bank.count(), 10);
+        assert_eq!(game.players[Color::Blue].state.bank.count(), 10);
+        assert!(game.court_owner(masson).is_none());
+    }
+
+    #[test]
+    fn test_betray_for_foreign_prize() {
+        let mut game = new_game();
+        let kakar = id("Haji Khan Kakar");
+        let macnaghten = id("William Hay Macnaghten");
+        let masson = id("Charles Masson");
+        let balkh = id("Balkh Arsenic Mine");
+
+        let red_state = red_mut(&mut game);
+        red_state.court.cards.extend([
+            in_court("Haji Khan Kakar"),
+            in_court("William Hay Macnaghten"),
+        ]);
+        red_state.prizes.cards.push(court_card("Charles Masson"));
+        let gift = red_state.bank.take_up_to(1);
+        red_state.gifts.add(gift);
+
+        let mut target = in_court("Balkh Arsenic Mine");
+        target
+            .spies
+            .add(Color::Red, red_mut(&mut game).bank.take_up_to(1));
+        game.players[Color::Blue]
+            .state
+            .court
+            .cards
+            .push_back(target);
+
+        let bank = red(&game).bank.count();
+        let betray = Action::Betray {
+            card: kakar,
+            target: balkh,
+            take_prize: true,
+        };
+        assert!(game.legal_actions().contains(&betray));
+
+        let events = game.apply(betray).unwrap();
+
+        assert_eq!(
+            events,
+            [
+                GameEvent::CardBetrayed {
+                    player: Color::Red,
+                    card: balkh,
+                    owner: Color::Blue,
+                },
+                GameEvent::CardDiscarded {
+                    player: Color::Red,
+                    card: macnaghten,
+                },
+                GameEvent::LoyaltyChanged {
+                    player: Color::Red,
+                    coalition: Coalition::Russia,
+                },
+                GameEvent::PrizeTaken {
+                    player: Color::Red,
+                    card: balkh,
+                },
+            ]
+        );
+
+        let red_state = red(&game);
+        assert_eq!(red_state.loyalty, Coalition::Russia);
+        assert_eq!(red_state.court.cards.len(), 1);
+        assert_eq!(
+            red_state
+                .prizes
+                .cards
+                .iter()
+                .map(|card| card.id())
+                .collect::<Vec<_>>(),
+            [balkh]
+        );
+        assert_eq!(red_state.gifts.count(), 0);
+        // The spy on the betrayed card and the gift both come back
+        assert_eq!(red_state.bank.count(), bank + 2);
+        assert!(game
+            .discard
+            .iter()
+            .any(|card| matches!(card, cards::Card::Court(card) if card.id() == masson)));
+    }
+
+    #[test]
+    fn test_betray_needs_a_spy() {
+        let mut game = new_game();
+        let burnes = id("Alexander Burnes");
+
+        red_mut(&mut game)
+            .court
+            .cards
+            .push_back(in_court("Alexander Burnes"));
+        game.players[Color::Blue]
+            .state
+            .court
+            .cards
+            .push_back(in_court("Charles Masson"));
+
+        assert_eq!(
+            game.apply(Action::Betray {
+                card: burnes,
+                target: id("Charles Masson"),
+                take_prize: false,
+            }),
+            Err(ActionError::NoSpy(id("Charles Masson")))
+        );
+        assert_eq!(red(&game).rupees.count(), 4);
+        assert!(!red(&game).court.get(burnes).unwrap().tapped);
+    }
+
+    #[test]
+    fn test_missing_card_action() {
+        let mut game = new_game();
+        let lenders = id("Money Lenders");
+        red_mut(&mut game)
+            .court
+            .cards
+            .push_back(in_court("Money Lenders"));
+
+        assert_eq!(
+            game.apply(Action::Tax {
+                card: lenders,
+                sources: vec![],
+            }),
+            Err(ActionError::MissingAction {
+                card: lenders,
+                action: CardAction::Tax
+            })
+        );
+
+        assert_eq!(
+            game.apply(Action::Gift {
+                card: id("Dost Mohammad")
+            }),
+            Err(ActionError::NotInCourt(id("Dost Mohammad")))
+        );
+    }
+
+    #[test]
+    fn test_action_serde() {
+        let action = Action::Play {
+            card: id("Money Lenders"),
+            side: Side::Left,
+            roads: vec![Border::new(Kabul, Punjab)],
+            spies: vec![],
+        };
+
+        let json = serde_json::to_string(&action).unwrap();
+        assert_eq!(serde_json::from_str::<Action>(&json).unwrap(), action);
+    }
+
+    /// Put event cards at the front of the top row of the market
+    fn with_events(game: &mut Game, names: &[&str]) {
+        let market_cards = names
+            .iter()
+            .map(|&name| id(name))
+            .chain((30..42 - names.len() as u8).map(|number| CardId::new(number).unwrap()));
+
+        game.market = Market::new();
+        game.market
+            .fill_from(&mut Deck::from_ids(market_cards).unwrap().draws());
+    }
+
+    fn buy(column: Column, choice: Option<EventChoice>) -> Action {
+        Action::Purchase {
+            row: Row::Top,
+            column,
+            choice,
+        }
+    }
+
+    #[test]
+    fn test_purchased_dominance_check_can_end_the_game() {
+        let game_with_lead = |lead| {
+            let mut game = new_game();
+            with_events(&mut game, &["Dominance Check"]);
+            red_mut(&mut game).score = lead;
+            game
+        };
+
+        // A three point lead isn't enough to end the game
+        let mut game = game_with_lead(3);
+        let events = game.apply(buy(Column::Zero, None)).unwrap();
+        assert_eq!(
+            events.last(),
+            Some(&GameEvent::DominanceCheck {
+                card: id("Dominance Check")
+            })
+        );
+        assert_eq!(game.game_over, None);
+
+        let mut game = game_with_lead(4);
+        let events = game.apply(buy(Column::Zero, None)).unwrap();
+        assert_eq!(
+            events.last(),
+            Some(&GameEvent::GameOver { winner: Color::Red })
+        );
+        assert_eq!(game.game_over, Some(GameOver { winner: Color::Red }));
+        assert!(game.legal_actions().is_empty());
+    }
+
+    #[test]
+    fn test_event_choices_are_checked() {
+        let mut game = new_game();
+        with_events(&mut game, &["Rumor"]);
+
+        assert_eq!(
+            game.validate(&buy(Column::Zero, None)),
+            Err(ActionError::ChoiceRequired(id("Rumor")))
+        );
+        assert_eq!(
+            game.validate(&buy(Column::Zero, Some(EventChoice::Rebuke(Region::Kabul)))),
+            Err(ActionError::InvalidChoice {
+                card: id("Rumor"),
+                choice: EventChoice::Rebuke(Region::Kabul),
+            })
+        );
+        assert_eq!(
+            game.validate(&buy(Column::One, Some(EventChoice::Rebuke(Region::Kabul)))),
+            Err(ActionError::InvalidChoice {
+                card: CardId::new(30).unwrap(),
+                choice: EventChoice::Rebuke(Region::Kabul),
+            })
+        );
+
+        // The target must be another player in the game
+        for target in [Color::Red, Color::Grey] {
+            assert_eq!(
+                game.validate(&buy(Column::Zero, Some(EventChoice::Rumor(target)))),
+                Err(ActionError::InvalidTarget(target))
+            );
+        }
+
+        game.apply(buy(Column::Zero, Some(EventChoice::Rumor(Color::Blue))))
+            .unwrap();
+        assert!(game.players[Color::Blue].state.effects.rumor);
+        assert!(!red(&game).effects.rumor);
+    }
+
+    #[test]
+    fn test_other_persuasive_methods() {
+        let mut game = new_game();
+        with_events(&mut game, &["Other Persuasive Methods"]);
+        red_mut(&mut game)
+            .hand
+            .cards
+            .push(court_card("Money Lenders"));
+        game.players[Color::Blue]
+            .state
+            .hand
+            .cards
+            .extend(["Kabul Bazaar", "Mir Masjidi"].map(court_card));
+
+        let events = game
+            .apply(buy(
+                Column::Zero,
+                Some(EventChoice::OtherPersuasiveMethods(Color::Blue)),
+            ))
+            .unwrap();
+
+        assert_eq!(
+            events[1],
+            GameEvent::HandsExchanged {
+                player: Color::Red,
+                with: Color::Blue,
+            }
+        );
+        assert_eq!(red(&game).hand.cards.len(), 2);
+        assert_eq!(
+            game.players[Color::Blue].state.hand.cards[0].id(),
+            id("Money Lenders")
+        );
+    }
+
+    #[test]
+    fn test_pashtunwali_values() {
+        let mut game = new_game();
+        with_events(&mut game, &["Pashtunwali Values"]);
+
+        let events = game
+            .apply(buy(
+                Column::Zero,
+                Some(EventChoice::PashtunwaliValues(Suit::Economic)),
+            ))
+            .unwrap();
+
+        assert_eq!(events[1], GameEvent::ClimateChanged(Suit::Economic));
+        assert!(game.effects.pashtunwali_values);
+
+        game.try_set_climate(Suit::Military);
+        assert_eq!(game.climate, Suit::Economic);
+    }
+
+    #[test]
+    fn test_rebuke() {
+        let mut game = new_game();
+        with_events(&mut game, &["Rebuke"]);
+
+        let armies = game.blocks.take_up_to(3, Coalition::Russia);
+        game.map.add_armies(Kabul, armies);
+
+        let events = game
+            .apply(buy(Column::Zero, Some(EventChoice::Rebuke(Kabul))))
+            .unwrap();
+
+        assert_eq!(
+            events[1],
+            GameEvent::ArmiesRemoved {
+                region: Kabul,
+                coalition: Coalition::Russia,
+                count: 3,
+            }
+        );
+        assert_eq!(game.map.total_block_counts()[Coalition::Russia], 0);
+        assert_eq!(game.blocks.count(Coalition::Russia), 12);
+    }
+
+    #[test]
+    fn test_public_withdrawal() {
+        let mut game = new_game();
+        with_events(&mut game, &["Public Withdrawal"]);
+
+        let withdrawal =
+            ActionError::Purchase(PurchaseError::Unpurchasable(id("Public Withdrawal")));
+        assert_eq!(
+            game.validate(&buy(Column::Zero, None)),
+            Err(withdrawal.clone())
+        );
+        assert!(!game.legal_actions().contains(&buy(Column::Zero, None)));
+
+        // Paying for the card to its right puts a rupee on it, which is
+        // removed from the game
+        let events = game.apply(buy(Column::One, None)).unwrap();
+        assert!(matches!(
+            &events[..],
+            [GameEvent::CardPurchased { receipt, .. }]
+                if receipt.payments == [Payment::Removed { row: Row::Top, column: Column::Zero }]
+        ));
+        assert_eq!(
+            game.market
+                .get_card(Row::Top, Column::Zero)
+                .unwrap()
+                .rupees(),
+            0
+        );
+        assert_eq!(red(&game).rupees.count(), 3);
+
+        // It still can't be bought with the next action
+        assert_eq!(game.apply(buy(Column::Zero, None)), Err(withdrawal));
+        assert_eq!(game.turn.actions_taken, 1);
+    }
+
+    #[test]
+    fn test_rumor_only_affects_target() {
+        let mut game = new_game();
+        with_events(&mut game, &["Rumor"]);
+
+        // Both players have a British patriot in court
+        red_mut(&mut game)
+            .court
+            .cards
+            .push_back(in_court("Mohan Lal"));
+        game.players[Color::Blue]
+            .state
+            .court
+            .cards
+            .push_back(in_court("Shah Shujah Durrani"));
+
+        let influence =
+            |game: &Game, color: Color| game.players[color].state.influence(&game.effects);
+        assert_eq!(influence(&game, Color::Red), 2);
+        assert_eq!(influence(&game, Color::Blue), 2);
+
+        let events = game
+            .apply(buy(Column::Zero, Some(EventChoice::Rumor(Color::Blue))))
+            .unwrap();
+
+        assert_eq!(
+            events[1],
+            GameEvent::EffectApplied {
+                player: Color::Blue,
+                effect: Effect::Rumor,
+            }
+        );
+        assert_eq!(influence(&game, Color::Red), 2);
+        assert_eq!(influence(&game, Color::Blue), 1);
+
+        // The rumor is forgotten at the next dominance check
+        game.resolve_dominance_check();
+        assert!(!game.players[Color::Blue].state.effects.rumor);
+        assert_eq!(influence(&game, Color::Blue), 2);
+    }
+}
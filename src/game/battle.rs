@@ -0,0 +1,514 @@
+use std::cmp;
+
+use crate::{
+    cards::{
+        court::{CardAction, SpecialAbility},
+        CardId,
+    },
+    player::Color,
+};
+
+use super::{action::CardUse, ActionError, BattleSite, BattleTarget, Game, GameEvent};
+
+impl Game {
+    /// Find a player's court card with a special ability, if they have one
+    fn ability_card(&self, player: Color, ability: SpecialAbility) -> Option<CardId> {
+        self.players[player]
+            .state
+            .court
+            .cards
+            .iter()
+            .find(|card| card.ability == Some(ability))
+            .map(|card| card.id())
+    }
+
+    /// The most pieces the current player could remove in a battle at a
+    /// site, not counting the rank of the card. In a region, that's their
+    /// loyal armies there, along with their tribes under Nationalism. On a
+    /// card, it's their spies there.
+    pub(super) fn battle_strength(&self, site: BattleSite) -> usize {
+        let player = self.current_player();
+
+        let strength = match site {
+            BattleSite::Region(region) => {
+                let tribes = match player.state.effects.nationalism {
+                    true => self.map.tribes(region).count(player.color),
+                    false => 0,
+                };
+
+                self.map.armies(region).count(player.state.loyalty) + tribes
+            }
+            BattleSite::Card(card) => self
+                .court_card(card)
+                .map_or(0, |card| card.spies.count(player.color)),
+        };
+
+        strength.max(0) as usize
+    }
+
+    /// The number of a target's pieces at a battle site
+    fn pieces_at(&self, site: BattleSite, target: BattleTarget) -> i8 {
+        match (site, target) {
+            (BattleSite::Region(region), BattleTarget::Army(coalition)) => {
+                self.map.armies(region).count(coalition)
+            }
+            (BattleSite::Region(region), BattleTarget::Road(border, coalition))
+                if border.borders(region) =>
+            {
+                self.map
+                    .roads_on(border)
+                    .map_or(0, |roads| roads.count(coalition))
+            }
+            (BattleSite::Region(region), BattleTarget::Tribe(player)) => {
+                self.map.tribes(region).count(player)
+            }
+            (BattleSite::Card(card), BattleTarget::Spy(player)) => self
+                .court_card(card)
+                .map_or(0, |card| card.spies.count(player)),
+            _ => 0,
+        }
+    }
+
+    /// Check that a piece belongs to an enemy of the current player, and
+    /// isn't protected by a special ability
+    fn check_battle_target(
+        &self,
+        site: BattleSite,
+        target: BattleTarget,
+    ) -> Result<(), ActionError> {
+        let player = self.current_player();
+        let loyalty = player.state.loyalty;
+
+        // Other players' spies are always enemies, whatever their loyalty
+        let enemy = match target {
+            BattleTarget::Army(coalition) | BattleTarget::Road(_, coalition) => {
+                coalition != loyalty
+            }
+            BattleTarget::Tribe(other) => {
+                other != player.color && self.players[other].state.loyalty != loyalty
+            }
+            BattleTarget::Spy(other) => other != player.color,
+        };
+
+        if !enemy {
+            return Err(ActionError::NotAnEnemy(target));
+        }
+
+        let protection = match (site, target) {
+            (BattleSite::Region(region), BattleTarget::Tribe(other)) => self
+                .ability_card(other, SpecialAbility::Citadel)
+                .filter(|&card| {
+                    self.court_card(card)
+                        .is_some_and(|card| card.region == region)
+                }),
+            (_, BattleTarget::Spy(other)) => {
+                self.ability_card(other, SpecialAbility::IndispensableAdvisors)
+            }
+            _ => None,
+        };
+
+        match protection {
+            Some(card) => Err(ActionError::ProtectedTarget { target, card }),
+            None => Ok(()),
+        }
+    }
+
+    /// Check that the current player can battle at a site with a court card.
+    /// Each target is a single enemy piece to remove, and a battle removes at
+    /// most as many pieces as the card's rank and the player's strength at the
+    /// site.
+    pub(super) fn check_battle(
+        &self,
+        card: CardId,
+        site: BattleSite,
+        targets: &[BattleTarget],
+    ) -> Result<CardUse, ActionError> {
+        let card_use = self.check_card_action(card, CardAction::Battle)?;
+        if targets.is_empty() {
+            return Err(ActionError::NothingToDo(CardAction::Battle));
+        }
+
+        if let BattleSite::Card(target) = site {
+            self.court_card(target)
+                .ok_or(ActionError::NotInAnyCourt(target))?;
+        }
+
+        let rank = self
+            .current_player()
+            .state
+            .court
+            .get(card)
+            .map_or(0, |card| card.rank.value());
+        let max = cmp::min(rank.max(0) as usize, self.battle_strength(site));
+
+        if targets.len() > max {
+            return Err(ActionError::TooManyTargets {
+                requested: targets.len(),
+                max,
+            });
+        }
+
+        for &target in targets {
+            self.check_battle_target(site, target)?;
+
+            let count = targets.iter().filter(|&&other| other == target).count();
+            if count as i8 > self.pieces_at(site, target) {
+                return Err(ActionError::NoTarget(target));
+            }
+        }
+
+        Ok(card_use)
+    }
+
+    /// Remove a single enemy piece in a battle
+    fn remove_battle_target(
+        &mut self,
+        site: BattleSite,
+        target: BattleTarget,
+        events: &mut Vec<GameEvent>,
+    ) {
+        match (site, target) {
+            (BattleSite::Region(region), BattleTarget::Army(coalition)) => {
+                let army = self.map.take_armies(region, 1, coalition);
+                self.blocks.add(army);
+                events.push(GameEvent::ArmiesRemoved {
+                    region,
+                    coalition,
+                    count: 1,
+                });
+            }
+            (BattleSite::Region(_), BattleTarget::Road(border, coalition)) => {
+                let road = self
+                    .map
+                    .remove_roads(border, 1, coalition)
+                    .expect("target was checked");
+                self.blocks.add(road);
+                events.push(GameEvent::RoadRemoved { border, coalition });
+            }
+            (BattleSite::Region(region), BattleTarget::Tribe(player)) => {
+                let last = self
+                    .remove_tribe(player, region)
+                    .expect("target was checked");
+                events.push(GameEvent::TribesRemoved {
+                    player,
+                    region,
+                    count: 1,
+                });
+
+                if last {
+                    self.overthrow(player, region, events);
+                }
+            }
+            (BattleSite::Card(card), BattleTarget::Spy(player)) => {
+                self.remove_spy(card, player, events)
+            }
+            _ => unreachable!("target was checked"),
+        }
+    }
+
+    /// Remove one of a player's spies from a card. It goes back to their
+    /// bank, unless they have a Safe House for it to return to.
+    fn remove_spy(&mut self, card: CardId, player: Color, events: &mut Vec<GameEvent>) {
+        let owner = self.court_owner(card).expect("target was checked");
+        let spy = self.players[owner]
+            .state
+            .court
+            .get_mut(card)
+            .expect("target was checked")
+            .spies
+            .take_up_to(player, 1);
+
+        events.push(GameEvent::SpyRemoved { player, card });
+
+        let safe_house = self
+            .ability_card(player, SpecialAbility::SafeHouse)
+            .filter(|&safe_house| safe_house != card);
+
+        match safe_house {
+            Some(safe_house) => {
+                self.players[player]
+                    .state
+                    .court
+                    .get_mut(safe_house)
+                    .expect("safe house is in the court")
+                    .spies
+                    .add(player, spy);
+                events.push(GameEvent::SpyPlaced {
+                    player,
+                    card: safe_house,
+                });
+            }
+            None => self.players[player].state.discard_cylinders(spy),
+        }
+    }
+
+    /// Remove enemy pieces at a site, one per target
+    pub(super) fn battle(
+        &mut self,
+        card: CardId,
+        site: BattleSite,
+        targets: &[BattleTarget],
+    ) -> Result<Vec<GameEvent>, ActionError> {
+        let card_use = self.check_battle(card, site, targets)?;
+
+        let mut events = Vec::new();
+        self.use_card(card, card_use, &mut events);
+
+        for &target in targets {
+            self.remove_battle_target(site, target, &mut events);
+        }
+
+        Ok(events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        cards::court::CardAction,
+        game::{
+            test_util::{id, in_court, new_game, red, red_mut},
+            Action,
+        },
+        map::{Border, Herat, Kabul, Persia, Punjab, Region, Transcaspia},
+        player::CourtCard,
+        primitives::Coalition,
+    };
+
+    fn battle(site: BattleSite, targets: Vec<BattleTarget>) -> Action {
+        Action::Battle {
+            card: id("Bala Hissar"),
+            site,
+            targets,
+        }
+    }
+
+    /// A game where red can battle with Bala Hissar, and blue is loyal to
+    /// Russia
+    fn battle_game() -> Game {
+        let mut game = new_game();
+        red_mut(&mut game)
+            .court
+            .cards
+            .push_back(in_court("Bala Hissar"));
+        game.players[Color::Blue].state.loyalty = Coalition::Russia;
+        game
+    }
+
+    fn add_armies(game: &mut Game, region: Region, count: i8, coalition: Coalition) {
+        let armies = game.blocks.take_up_to(count, coalition);
+        game.map.add_armies(region, armies);
+    }
+
+    fn add_spies(game: &mut Game, card: &mut CourtCard, player: Color, count: i8) {
+        let spies = game.players[player].state.bank.take_up_to(count);
+        card.spies.add(player, spies);
+    }
+
+    #[test]
+    fn test_battle_in_a_region() {
+        let mut game = battle_game();
+        let road = Border::new(Kabul, Punjab);
+
+        add_armies(&mut game, Kabul, 2, Coalition::Britain);
+        add_armies(&mut game, Kabul, 1, Coalition::Russia);
+        let russian_road = game.blocks.take_up_to(1, Coalition::Russia);
+        game.map.add_roads(road, russian_road).unwrap();
+        game.place_tribe(Color::Blue, Kabul).unwrap();
+        game.players[Color::Blue]
+            .state
+            .court
+            .cards
+            .push_back(in_court("Murad Beg"));
+
+        let kabul = BattleSite::Region(Kabul);
+        assert_eq!(
+            game.validate(&battle(kabul, vec![])),
+            Err(ActionError::NothingToDo(CardAction::Battle))
+        );
+        assert_eq!(
+            game.validate(&battle(kabul, vec![BattleTarget::Army(Coalition::Britain)])),
+            Err(ActionError::NotAnEnemy(BattleTarget::Army(
+                Coalition::Britain
+            )))
+        );
+        assert_eq!(
+            game.validate(&battle(
+                kabul,
+                vec![BattleTarget::Army(Coalition::Russia); 3]
+            )),
+            Err(ActionError::TooManyTargets {
+                requested: 3,
+                max: 2
+            })
+        );
+        assert_eq!(
+            game.validate(&battle(
+                kabul,
+                vec![BattleTarget::Army(Coalition::Russia); 2]
+            )),
+            Err(ActionError::NoTarget(BattleTarget::Army(Coalition::Russia)))
+        );
+
+        let far_road = BattleTarget::Road(Border::new(Herat, Persia), Coalition::Russia);
+        assert_eq!(
+            game.validate(&battle(kabul, vec![far_road])),
+            Err(ActionError::NoTarget(far_road))
+        );
+
+        // Without armies in Punjab, red has no strength there
+        assert_eq!(
+            game.validate(&battle(
+                BattleSite::Region(Punjab),
+                vec![BattleTarget::Road(road, Coalition::Russia)]
+            )),
+            Err(ActionError::TooManyTargets {
+                requested: 1,
+                max: 0
+            })
+        );
+
+        let targets = vec![
+            BattleTarget::Road(road, Coalition::Russia),
+            BattleTarget::Tribe(Color::Blue),
+        ];
+        assert!(game
+            .legal_actions()
+            .contains(&battle(kabul, targets.clone())));
+
+        let events = game.apply(battle(kabul, targets)).unwrap();
+
+        assert_eq!(
+            events,
+            [
+                GameEvent::RoadRemoved {
+                    border: road,
+                    coalition: Coalition::Russia,
+                },
+                GameEvent::TribesRemoved {
+                    player: Color::Blue,
+                    region: Kabul,
+                    count: 1,
+                },
+                GameEvent::CardOverthrown {
+                    player: Color::Blue,
+                    card: id("Murad Beg"),
+                },
+            ]
+        );
+        assert_eq!(game.map.roads_on(road).unwrap().count(Coalition::Russia), 0);
+        assert_eq!(game.map.tribes(Kabul).count(Color::Blue), 0);
+        assert!(game.players[Color::Blue].state.court.cards.is_empty());
+        assert!(red(&game).court.get(id("Bala Hissar")).unwrap().tapped);
+    }
+
+    #[test]
+    fn test_battle_spies() {
+        let mut game = battle_game();
+
+        let mut target = in_court("Murad Beg");
+        add_spies(&mut game, &mut target, Color::Red, 1);
+        add_spies(&mut game, &mut target, Color::Blue, 2);
+        game.players[Color::Blue]
+            .state
+            .court
+            .cards
+            .extend([target, in_court("Herat Guild of Couriers")]);
+
+        let site = BattleSite::Card(id("Murad Beg"));
+        assert_eq!(
+            game.validate(&battle(site, vec![BattleTarget::Spy(Color::Red)])),
+            Err(ActionError::NotAnEnemy(BattleTarget::Spy(Color::Red)))
+        );
+        assert_eq!(
+            game.validate(&battle(site, vec![BattleTarget::Spy(Color::Blue); 2])),
+            Err(ActionError::TooManyTargets {
+                requested: 2,
+                max: 1
+            })
+        );
+        assert_eq!(
+            game.validate(&battle(
+                BattleSite::Card(id("Dost Mohammad")),
+                vec![BattleTarget::Spy(Color::Blue)]
+            )),
+            Err(ActionError::NotInAnyCourt(id("Dost Mohammad")))
+        );
+
+        let events = game
+            .apply(battle(site, vec![BattleTarget::Spy(Color::Blue)]))
+            .unwrap();
+
+        // Blue's spy escapes to their safe house
+        assert_eq!(
+            events,
+            [
+                GameEvent::SpyRemoved {
+                    player: Color::Blue,
+                    card: id("Murad Beg"),
+                },
+                GameEvent::SpyPlaced {
+                    player: Color::Blue,
+                    card: id("Herat Guild of Couriers"),
+                },
+            ]
+        );
+        let court = &game.players[Color::Blue].state.court;
+        assert_eq!(
+            court.get(id("Murad Beg")).unwrap().spies.count(Color::Blue),
+            1
+        );
+        assert_eq!(
+            court
+                .get(id("Herat Guild of Couriers"))
+                .unwrap()
+                .spies
+                .count(Color::Blue),
+            1
+        );
+    }
+
+    #[test]
+    fn test_protected_targets() {
+        let mut game = battle_game();
+
+        add_armies(&mut game, Transcaspia, 1, Coalition::Britain);
+        game.place_tribe(Color::Blue, Transcaspia).unwrap();
+
+        let mut target = in_court("Murad Beg");
+        add_spies(&mut game, &mut target, Color::Red, 1);
+        add_spies(&mut game, &mut target, Color::Blue, 1);
+        game.players[Color::Blue].state.court.cards.extend([
+            target,
+            in_court("Ark of Bukhara"),
+            in_court("Claude Wade"),
+        ]);
+
+        assert_eq!(
+            game.validate(&battle(
+                BattleSite::Region(Transcaspia),
+                vec![BattleTarget::Tribe(Color::Blue)]
+            )),
+            Err(ActionError::ProtectedTarget {
+                target: BattleTarget::Tribe(Color::Blue),
+                card: id("Ark of Bukhara"),
+            })
+        );
+        assert_eq!(
+            game.validate(&battle(
+                BattleSite::Card(id("Murad Beg")),
+                vec![BattleTarget::Spy(Color::Blue)]
+            )),
+            Err(ActionError::ProtectedTarget {
+                target: BattleTarget::Spy(Color::Blue),
+                card: id("Claude Wade"),
+            })
+        );
+        assert!(!game
+            .legal_actions()
+            .iter()
+            .any(|action| matches!(action, Action::Battle { .. })));
+    }
+}
@@ -0,0 +1,245 @@
+use crate::{
+    cards::{court::CardAction, CardId},
+    map::Region,
+};
+
+use super::{
+    action::{check_funds, CardUse},
+    ActionError, BuildTarget, Game, GameEvent,
+};
+
+impl Game {
+    /// Check that the current player can place a block at a site. Armies go
+    /// in regions they rule, and roads on borders of regions they rule.
+    fn check_build_target(&self, target: BuildTarget) -> Result<(), ActionError> {
+        let color = self.current_player().color;
+        let rules = |region: Region| self.map.ruler(region, &self.players) == Some(color);
+
+        match target {
+            BuildTarget::Army(region) if !rules(region) => Err(ActionError::NotRuled(region)),
+            BuildTarget::Road(border) => {
+                self.map.roads_on(border)?;
+
+                match rules(border.front()) || rules(border.back()) {
+                    true => Ok(()),
+                    false => Err(ActionError::NotRuledBorder(border)),
+                }
+            }
+            BuildTarget::Army(_) => Ok(()),
+        }
+    }
+
+    /// Check that the current player can build a block at each target with
+    /// a court card. Returns the cost of the build.
+    pub(super) fn check_build(
+        &self,
+        card: CardId,
+        targets: &[BuildTarget],
+    ) -> Result<(CardUse, i8), ActionError> {
+        let card_use = self.check_card_action(card, CardAction::Build)?;
+        if targets.is_empty() {
+            return Err(ActionError::NothingToDo(CardAction::Build));
+        }
+
+        let player = &self.current_player().state;
+        let max = self.pricing().max_builds(player);
+
+        if targets.len() > max {
+            return Err(ActionError::TooManyBlocks {
+                requested: targets.len(),
+                max,
+            });
+        }
+
+        for &target in targets {
+            self.check_build_target(target)?;
+        }
+
+        let coalition = player.loyalty;
+        let available = self.blocks.count(coalition);
+        if targets.len() > available as usize {
+            return Err(ActionError::NotEnoughBlocks {
+                coalition,
+                requested: targets.len(),
+                available,
+            });
+        }
+
+        let cost = self.pricing().build_cost(player, targets.len());
+        check_funds(player, cost + card_use.bribe_amount())?;
+
+        Ok((card_use, cost))
+    }
+
+    /// Build armies and roads from the supply of the current player's
+    /// coalition. The cost is paid into the market.
+    pub(super) fn build(
+        &mut self,
+        card: CardId,
+        targets: &[BuildTarget],
+    ) -> Result<Vec<GameEvent>, ActionError> {
+        let (card_use, cost) = self.check_build(card, targets)?;
+
+        let mut events = Vec::new();
+        self.use_card(card, card_use, &mut events);
+
+        let payment = self.current_player_mut().state.rupees.take_up_to(cost);
+        self.market.spend_for_ability(payment);
+
+        let coalition = self.current_player().state.loyalty;
+
+        for &target in targets {
+            let block = self.blocks.take_up_to(1, coalition);
+
+            match target {
+                BuildTarget::Army(region) => {
+                    self.map.add_armies(region, block);
+                    events.push(GameEvent::ArmiesPlaced {
+                        region,
+                        coalition,
+                        count: 1,
+                    });
+                }
+                BuildTarget::Road(border) => {
+                    self.map
+                        .add_roads(border, block)
+                        .expect("roads were checked");
+                    events.push(GameEvent::RoadPlaced { border, coalition });
+                }
+            }
+        }
+
+        Ok(events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        game::{
+            test_util::{id, in_court, new_game, red, red_mut},
+            Action,
+        },
+        map::{Border, Herat, Kabul, Persia, Punjab},
+        market::{Column, Row},
+        player::Color,
+        primitives::Coalition,
+    };
+
+    fn build(targets: Vec<BuildTarget>) -> Action {
+        Action::Build {
+            card: id("Money Lenders"),
+            targets,
+        }
+    }
+
+    fn build_game() -> Game {
+        let mut game = new_game();
+        red_mut(&mut game)
+            .court
+            .cards
+            .push_back(in_court("Money Lenders"));
+        game
+    }
+
+    #[test]
+    fn test_build() {
+        let mut game = build_game();
+        let road = Border::new(Kabul, Punjab);
+
+        assert_eq!(
+            game.validate(&build(vec![BuildTarget::Army(Kabul)])),
+            Err(ActionError::NotRuled(Kabul))
+        );
+
+        game.place_tribe(Color::Red, Kabul).unwrap();
+        assert_eq!(
+            game.validate(&build(vec![BuildTarget::Road(Border::new(Herat, Persia))])),
+            Err(ActionError::NotRuledBorder(Border::new(Herat, Persia)))
+        );
+        assert_eq!(
+            game.validate(&build(vec![BuildTarget::Army(Kabul); 4])),
+            Err(ActionError::TooManyBlocks {
+                requested: 4,
+                max: 3
+            })
+        );
+        assert_eq!(
+            game.validate(&build(vec![BuildTarget::Army(Kabul); 3])),
+            Err(ActionError::CannotAfford {
+                cost: 6,
+                available: 4
+            })
+        );
+        assert!(game.legal_actions().contains(&build(vec![
+            BuildTarget::Army(Kabul),
+            BuildTarget::Road(road)
+        ])));
+
+        let events = game
+            .apply(build(vec![
+                BuildTarget::Army(Kabul),
+                BuildTarget::Road(road),
+            ]))
+            .unwrap();
+
+        assert_eq!(
+            events,
+            [
+                GameEvent::ArmiesPlaced {
+                    region: Kabul,
+                    coalition: Coalition::Britain,
+                    count: 1,
+                },
+                GameEvent::RoadPlaced {
+                    border: road,
+                    coalition: Coalition::Britain,
+                },
+            ]
+        );
+        assert_eq!(game.map.armies(Kabul).count(Coalition::Britain), 1);
+        assert_eq!(
+            game.map.roads_on(road).unwrap().count(Coalition::Britain),
+            1
+        );
+        assert_eq!(game.blocks.count(Coalition::Britain), 10);
+
+        // The rupees went into the market
+        assert_eq!(red(&game).rupees.count(), 0);
+        let market_rupees: i8 = [Row::Top, Row::Bottom]
+            .into_iter()
+            .flat_map(|row| Column::all().map(move |column| (row, column)))
+            .filter_map(|(row, column)| game.market.get_card(row, column))
+            .map(|card| card.rupees())
+            .sum();
+        assert_eq!(market_rupees, 4);
+    }
+
+    #[test]
+    fn test_build_limits() {
+        let mut game = build_game();
+        let pricing = game.pricing();
+        let player = red(&game);
+        assert_eq!(pricing.max_builds(player), 3);
+        assert_eq!(pricing.build_cost(player, 3), 6);
+
+        red_mut(&mut game).effects.nation_building = true;
+        let pricing = game.pricing();
+        let player = red(&game);
+        assert_eq!(pricing.max_builds(player), 6);
+        assert_eq!(pricing.build_cost(player, 3), 4);
+
+        red_mut(&mut game)
+            .court
+            .cards
+            .push_back(in_court("Bolan Pass"));
+        let pricing = game.pricing();
+        let player = red(&game);
+        assert_eq!(pricing.max_builds(player), 7);
+        assert_eq!(pricing.build_cost(player, 1), 2);
+        assert_eq!(pricing.build_cost(player, 3), 2);
+        assert_eq!(pricing.build_cost(player, 7), 6);
+    }
+}
@@ -1,25 +1,63 @@
-use std::{cmp::Reverse, iter};
+use std::cmp::{self, Reverse};
 
 use enum_map::{enum_map, EnumMap};
 
-use crate::{cards::court::SpecialAbility, player, primitives::Coalition, score};
+use serde::{Deserialize, Serialize};
 
-use super::Game;
+use crate::{
+    cards::{self, court::SpecialAbility, CardId},
+    player,
+    primitives::Coalition,
+    score,
+};
+
+use super::{Decision, Game, GameEvent, Request};
+
+/// The number of armies each Insurrection places after a dominance check
+const INSURRECTION_ARMIES: i8 = 2;
+
+/// The lead in victory points that ends the game early
+const WINNING_LEAD: i8 = 4;
 
 struct BlockCount {
     coalition: Coalition,
     count: i8,
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct GameOver {
     pub winner: player::Color,
 }
 
 impl Game {
+    pub(super) fn check_not_over(&self) -> Result<(), GameOver> {
+        match self.game_over {
+            Some(game_over) => Err(game_over),
+            None => Ok(()),
+        }
+    }
+
+    /// Discard a dominance card that's left the market and resolve the check
+    /// it triggers
+    pub(super) fn dominance_check(&mut self, card: CardId, events: &mut Vec<GameEvent>) {
+        self.discard.push(cards::Card::from_id(card));
+        let game_over = self.resolve_dominance_check();
+        events.push(GameEvent::DominanceCheck { card });
+
+        if let Some(GameOver { winner }) = game_over {
+            events.push(GameEvent::GameOver { winner });
+        }
+    }
+
     /// Resolve a dominance check. Run this method *after* discarding any
     /// dominance cards, as it examines the discard pile to determine if this
     /// is the final
+    ///
+    /// The game ends after the final check, or once any player leads everyone
+    /// else by 4 or more victory points. The highest score wins, with ties
+    /// going to the player with the most rupees, and then to the earliest in
+    /// turn order. Once the game is over, any outstanding requests are dropped
+    /// and no Insurrections are resolved.
     pub fn resolve_dominance_check(&mut self) -> Option<GameOver> {
         // Is this the final dominance check? If so, it scores double, and the
         // game is definitely over
@@ -117,10 +155,16 @@ impl Game {
             .iter_mut()
             .for_each(|player| player.state.effects.clear());
 
-        // Resolve insurrections
-        // TODO: if there are no blocks in the supply, the player should take them
-        // from elsewhere
-        self.players
+        if let Some(game_over) = self.standings(final_dominance_check) {
+            self.game_over = Some(game_over);
+            self.requests.clear();
+            return Some(game_over);
+        }
+
+        // Resolve insurrections. If there aren't enough blocks in the supply,
+        // the player has to choose blocks to take from the map instead
+        let insurrections: Vec<_> = self
+            .players
             .iter()
             .flat_map(|player| {
                 player
@@ -129,14 +173,52 @@ impl Game {
                     .cards
                     .iter()
                     .filter(|card| card.ability == Some(SpecialAbility::Insurrection))
-                    .map(|card| card.region)
-                    .zip(iter::repeat(player.state.loyalty))
+                    .map(move |card| (player.color, card.region, player.state.loyalty))
             })
-            .for_each(|(region, coalition)| {
-                let armies = self.blocks.take_up_to(2, coalition);
-                self.map.add_armies(region, armies);
-            });
+            .collect();
+
+        for (player, region, coalition) in insurrections {
+            let armies = self.blocks.take_up_to(INSURRECTION_ARMIES, coalition);
+            let missing = INSURRECTION_ARMIES - armies.count(coalition);
+            self.map.add_armies(region, armies);
+
+            // Armies already in the region can't be moved into it
+            let available =
+                self.map.total_block_counts()[coalition] - self.map.armies(region).count(coalition);
+
+            let count = cmp::min(missing, available);
+            if count > 0 {
+                self.requests.push(Request {
+                    player,
+                    decision: Decision::InsurrectionArmies {
+                        region,
+                        coalition,
+                        count,
+                    },
+                });
+            }
+        }
 
         None
     }
+
+    /// Whether the scores decide the game, and who won if so
+    fn standings(&self, final_dominance_check: bool) -> Option<GameOver> {
+        let mut standings: Vec<_> = self
+            .players
+            .iter()
+            .map(|player| {
+                let state = &player.state;
+                (player.color, state.score, state.rupees.count())
+            })
+            .collect();
+
+        // Stable, so ties that remain stay in turn order
+        standings.sort_by_key(|&(_, score, rupees)| Reverse((score, rupees)));
+
+        let (winner, best, _) = standings[0];
+        let lead = best - standings.get(1).map_or(0, |&(_, score, _)| score);
+
+        (final_dominance_check || lead >= WINNING_LEAD).then_some(GameOver { winner })
+    }
 }
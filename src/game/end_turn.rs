@@ -1,16 +1,398 @@
 use std::collections::HashSet;
 
-use super::Game;
+use serde::{Deserialize, Serialize};
+
+use crate::{cards, primitives::Suit};
+
+use super::{ActionError, Game, GameEvent};
 
 /// The set of cards the player will discard at the end of their turn
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Discards {
     pub court: HashSet<usize>,
     pub hand: HashSet<usize>,
 }
 
-
+/// Check that every index refers to a card in a pile of `len` cards
+fn check_indices(
+    pile: &'static str,
+    indices: &HashSet<usize>,
+    len: usize,
+) -> Result<(), ActionError> {
+    match indices.iter().find(|&&index| index >= len) {
+        Some(&index) => Err(ActionError::InvalidDiscard { pile, index }),
+        None => Ok(()),
+    }
+}
 
 impl Game {
-    pub fn end_turn(&mut self, discards: Discards)
+    /// The number of cards the current player must discard from their hand
+    /// at the end of their turn, once the given court cards are gone. Court
+    /// cards are discarded first, so losing intelligence cards from the court
+    /// lowers the hand limit.
+    pub(super) fn hand_discards_needed(&self, court_discards: &HashSet<usize>) -> usize {
+        let player = &self.current_player().state;
+
+        let lost_stars: i8 = court_discards
+            .iter()
+            .filter_map(|&index| player.court.cards.get(index))
+            .filter(|card| card.suit == Suit::Intelligence)
+            .map(|card| card.rank.value())
+            .sum();
+
+        let hand_size = (player.hand_size() - lost_stars).max(0) as usize;
+        player.hand.cards.len().saturating_sub(hand_size)
+    }
+
+    /// Check that the current player can end their turn with the given
+    /// discards. They must discard exactly enough court cards to get down to
+    /// their court size, and then exactly enough cards from their hand to get
+    /// down to their hand size.
+    pub(super) fn check_end_turn(&self, discards: &Discards) -> Result<(), ActionError> {
+        self.check_no_requests().map_err(ActionError::WaitingOn)?;
+
+        let player = &self.current_player().state;
+
+        check_indices("court", &discards.court, player.court.cards.len())?;
+        check_indices("hand", &discards.hand, player.hand.cards.len())?;
+
+        if discards.court.len() != player.court_overflow() {
+            return Err(ActionError::WrongCount {
+                piece: "court discards",
+                expected: player.court_overflow(),
+                found: discards.court.len(),
+            });
+        }
+
+        let hand_discards = self.hand_discards_needed(&discards.court);
+        if discards.hand.len() != hand_discards {
+            return Err(ActionError::WrongCount {
+                piece: "hand discards",
+                expected: hand_discards,
+                found: discards.hand.len(),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// End the current player's turn. The discards are checked against the
+    /// player's court and hand limits, then the cards are discarded (returning
+    /// any spies on court cards to their owners), court cards are untapped,
+    /// the market is cleaned up, and play passes to the next player. If the
+    /// discards are rejected, nothing is changed.
+    ///
+    /// Discarding a court card with leverage can leave the player owing more
+    /// discards. The turn then waits for them to answer those requests, and
+    /// finishes after the last one.
+    pub fn end_turn(&mut self, discards: Discards) -> Result<Vec<GameEvent>, ActionError> {
+        self.check_end_turn(&discards)?;
+
+        let mut events = Vec::new();
+        let color = self.current_player().color;
+
+        // Find the court cards by id first, since removing them shifts the
+        // rest of the court
+        let court_discards: Vec<_> = self
+            .current_player()
+            .state
+            .court
+            .cards
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| discards.court.contains(index))
+            .map(|(_, card)| card.id())
+            .collect();
+
+        for id in court_discards {
+            self.discard_court_card(color, id, &mut events);
+        }
+
+        let mut hand_discards: Vec<usize> = discards.hand.into_iter().collect();
+        hand_discards.sort_unstable();
+
+        let player = &mut self.current_player_mut().state;
+        let discarded: Vec<_> = hand_discards
+            .into_iter()
+            .rev()
+            .map(|index| player.hand.cards.remove(index))
+            .collect();
+
+        // Report the hand discards from left to right
+        for card in discarded.into_iter().rev() {
+            events.push(GameEvent::CardDiscarded {
+                player: color,
+                card: card.id(),
+            });
+            self.discard.push(cards::Card::Court(card));
+        }
+
+        self.turn.ending = true;
+        self.resume_end_turn(&mut events);
+
+        Ok(events)
+    }
+
+    /// Finish ending the turn, if it's ending and nobody has anything left to
+    /// decide. Called again after every response.
+    pub(super) fn resume_end_turn(&mut self, events: &mut Vec<GameEvent>) {
+        if !self.turn.ending || !self.requests.is_empty() {
+            return;
+        }
+
+        let color = self.current_player().color;
+        self.turn.ending = false;
+
+        self.current_player_mut()
+            .state
+            .court
+            .cards
+            .iter_mut()
+            .for_each(|card| card.tapped = false);
+
+        events.extend(self.cleanup_market());
+
+        if self.game_over.is_some() {
+            return;
+        }
+
+        self.turn.player = (self.turn.player + 1) % self.players.players().len();
+        self.turn.actions_taken = 0;
+
+        events.push(GameEvent::TurnEnded {
+            player: color,
+            next: self.current_player().color,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        cards::CardId,
+        deck::Deck,
+        game::{
+            test_util::{court_card, game_with, in_court, red_mut},
+            Action, Decision, Response,
+        },
+        player::Color,
+        primitives::Coalition,
+    };
+
+    fn new_game() -> Game {
+        game_with(
+            &[Color::Red, Color::Blue, Color::Black],
+            Coalition::Russia,
+            6,
+        )
+    }
+
+    fn discards(court: &[usize], hand: &[usize]) -> Discards {
+        Discards {
+            court: court.iter().copied().collect(),
+            hand: hand.iter().copied().collect(),
+        }
+    }
+
+    #[test]
+    fn test_end_turn() {
+        let mut game = new_game();
+        game.deck = Deck::default();
+        game.turn.actions_taken = 2;
+
+        let red = red_mut(&mut game);
+        let mut tapped = in_court("Kabul Bazaar");
+        tapped.tapped = true;
+        red.court.cards.push_back(tapped);
+        red.hand
+            .cards
+            .extend(["Money Lenders", "Mir Masjidi", "Murad Beg"].map(court_card));
+
+        let events = game.apply(Action::EndTurn(discards(&[], &[0]))).unwrap();
+
+        // Anything else is from the market cleanup
+        assert_eq!(
+            events.first(),
+            Some(&GameEvent::CardDiscarded {
+                player: Color::Red,
+                card: CardId::from_name("Money Lenders").unwrap(),
+            })
+        );
+        assert_eq!(
+            events.last(),
+            Some(&GameEvent::TurnEnded {
+                player: Color::Red,
+                next: Color::Blue,
+            })
+        );
+
+        let red = &game.players[Color::Red].state;
+        assert_eq!(red.hand.cards.len(), 2);
+        assert!(!red.court.cards[0].tapped);
+        assert!(game
+            .discard
+            .iter()
+            .any(|card| card.id() == CardId::from_name("Money Lenders").unwrap()));
+
+        assert_eq!(game.turn.player, 1);
+        assert_eq!(game.turn.actions_taken, 0);
+    }
+
+    #[test]
+    fn test_turn_order_wraps() {
+        let mut game = new_game();
+        game.turn.player = 2;
+
+        game.end_turn(Discards::default()).unwrap();
+        assert_eq!(game.current_player().color, Color::Red);
+    }
+
+    #[test]
+    fn test_wrong_discards() {
+        let mut game = new_game();
+        red_mut(&mut game)
+            .hand
+            .cards
+            .extend(["Money Lenders", "Mir Masjidi", "Murad Beg"].map(court_card));
+
+        assert_eq!(
+            game.end_turn(Discards::default()),
+            Err(ActionError::WrongCount {
+                piece: "hand discards",
+                expected: 1,
+                found: 0,
+            })
+        );
+        assert_eq!(
+            game.end_turn(discards(&[], &[0, 1])),
+            Err(ActionError::WrongCount {
+                piece: "hand discards",
+                expected: 1,
+                found: 2,
+            })
+        );
+        assert_eq!(
+            game.end_turn(discards(&[], &[3])),
+            Err(ActionError::InvalidDiscard {
+                pile: "hand",
+                index: 3,
+            })
+        );
+        assert_eq!(
+            game.end_turn(discards(&[0], &[0])),
+            Err(ActionError::InvalidDiscard {
+                pile: "court",
+                index: 0,
+            })
+        );
+
+        // Nothing changed
+        assert_eq!(game.players[Color::Red].state.hand.cards.len(), 3);
+        assert_eq!(game.turn.player, 0);
+    }
+
+    #[test]
+    fn test_court_discards_come_first() {
+        let mut game = new_game();
+
+        // A court of four with no political stars is one over the limit.
+        // Mohan Lal's blue star raises the hand limit to 3, so if it's
+        // discarded, a card from the hand has to go too.
+        let red = red_mut(&mut game);
+        red.court.cards.extend(
+            [
+                "Mohan Lal",
+                "Kabul Bazaar",
+                "Money Lenders",
+                "Jan-Fishan Khan",
+            ]
+            .map(in_court),
+        );
+        red.hand
+            .cards
+            .extend(["Mir Masjidi", "Murad Beg", "Bala Hissar"].map(court_card));
+
+        let spy = game.players[Color::Blue].state.bank.take_up_to(1);
+        red_mut(&mut game).court.cards[0]
+            .spies
+            .add(Color::Blue, spy);
+
+        assert_eq!(
+            game.check_end_turn(&discards(&[0], &[])),
+            Err(ActionError::WrongCount {
+                piece: "hand discards",
+                expected: 1,
+                found: 0,
+            })
+        );
+        assert!(game.check_end_turn(&discards(&[1], &[])).is_ok());
+
+        game.end_turn(discards(&[0], &[2])).unwrap();
+
+        let red = &game.players[Color::Red].state;
+        assert_eq!(red.court.cards.len(), 3);
+        assert_eq!(red.court.cards[0].name, "Kabul Bazaar");
+        assert_eq!(red.hand.cards.len(), 2);
+
+        // Blue's spy went home with the discarded card
+        assert_eq!(game.players[Color::Blue].state.bank.count(), 10);
+    }
+
+    #[test]
+    fn test_leverage_debt_delays_the_end_of_the_turn() {
+        let mut game = new_game();
+        game.deck = Deck::default();
+
+        // Money Lenders has leverage, and red has no rupees to pay it back
+        let red = red_mut(&mut game);
+        red.court.cards.extend(
+            [
+                "Money Lenders",
+                "Kabul Bazaar",
+                "Mir Masjidi",
+                "Jan-Fishan Khan",
+            ]
+            .map(in_court),
+        );
+        red.hand.cards.push(court_card("Bala Hissar"));
+        red.rupees.take_up_to(red.rupees.count());
+
+        let events = game.end_turn(discards(&[0], &[])).unwrap();
+        assert_eq!(
+            events,
+            [
+                GameEvent::CardDiscarded {
+                    player: Color::Red,
+                    card: CardId::from_name("Money Lenders").unwrap(),
+                },
+                GameEvent::LeverageRepaid {
+                    player: Color::Red,
+                    card: CardId::from_name("Money Lenders").unwrap(),
+                    amount: 0,
+                },
+            ]
+        );
+        assert_eq!(
+            game.requests.players_owing(Decision::LeverageDebt),
+            [Color::Red]
+        );
+        assert_eq!(game.requests.len(), 2);
+        assert_eq!(game.turn.player, 0);
+
+        game.respond(Color::Red, Response::Discard(0)).unwrap();
+        assert_eq!(game.turn.player, 0);
+
+        let events = game.respond(Color::Red, Response::DiscardCourt(0)).unwrap();
+        assert_eq!(
+            events.last(),
+            Some(&GameEvent::TurnEnded {
+                player: Color::Red,
+                next: Color::Blue,
+            })
+        );
+        assert_eq!(game.players[Color::Red].state.court.cards.len(), 2);
+        assert!(game.players[Color::Red].state.hand.cards.is_empty());
+    }
 }
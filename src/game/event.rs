@@ -1,38 +1,84 @@
-use enum_map::EnumMap;
+use std::mem;
+
+use itertools::Itertools;
 
 use crate::{
     cards::{
         self,
-        event::{DiscardEvent, PurchaseEvent},
+        event::{self as event_card, DiscardEvent, PurchaseEvent},
     },
-    player::Color,
+    map::Region,
+    market::MarketEvent,
+    player::{Color, Effect},
     rupees::RupeeSet,
 };
 
-use super::Game;
+use super::{Decision, EventChoice, Game, GameEvent, Request};
 
-/// When Confidence Failure is resolved, all players must discard a card from
-/// their hand. This struct captures all player's choices.
-#[derive(Debug, Clone, Copy, Default)]
-pub struct DiscardChoices {
-    pub choices: EnumMap<Color, Option<usize>>,
-}
+impl Game {
+    /// Clean up the market at the end of a turn, and resolve every card that
+    /// leaves it. Event cards discarded from the leftmost column have their
+    /// discard effect applied, and dominance checks are resolved once they're
+    /// in the discard pile. Any decisions players have to make as a result
+    /// are added to the request queue.
+    pub fn cleanup_market(&mut self) -> Vec<GameEvent> {
+        let mut events = Vec::new();
 
-/// When Confidence Failure is resolved, if any players didn't designate a card
-/// to discard, this struct indicates which players must do so.
-pub struct MustDiscard {
-    /// The set of players who must designate a discard and didn't do so
-    pub absent_players: (),
-}
+        for market_event in self.market.cleanup(&mut self.deck) {
+            match market_event {
+                MarketEvent::EventDiscarded { card, rupees, .. } => {
+                    events.push(GameEvent::EventDiscarded {
+                        card: card.id(),
+                        rupees,
+                    });
 
-impl Game {
-    fn apply_discard_event(&mut self, event: DiscardEvent) {
+                    self.apply_discard_event(card.discard, &mut events);
+                    self.discard
+                        .push(cards::Card::Event(event_card::Card::Event(card)));
+                }
+                MarketEvent::DominanceCheck { card, rupees, .. } => {
+                    events.push(GameEvent::EventDiscarded { card, rupees });
+
+                    self.dominance_check(card, &mut events);
+                }
+                MarketEvent::Dealt { row, column, card } => {
+                    events.push(GameEvent::CardDealt { row, column, card })
+                }
+            }
+        }
+
+        events
+    }
+
+    fn apply_discard_event(&mut self, event: DiscardEvent, events: &mut Vec<GameEvent>) {
         match event {
-            DiscardEvent::ChangeSuit(suit) => self.try_set_climate(suit),
-            DiscardEvent::Riots(_) => todo!(),
+            DiscardEvent::ChangeSuit(suit) => {
+                let climate = self.climate;
+                self.try_set_climate(suit);
+
+                if self.climate != climate {
+                    events.push(GameEvent::ClimateChanged(self.climate));
+                }
+            }
+            DiscardEvent::Riots(region) => self.riot(region, events),
             DiscardEvent::DisregardForCustoms => self.effects.disregard_for_customs = true,
             DiscardEvent::NoEffect => {}
-            DiscardEvent::ConfidenceFailure => todo!(),
+            DiscardEvent::ConfidenceFailure => {
+                // Players with nothing in hand have nothing to discard
+                let requests = self
+                    .players
+                    .iter()
+                    .filter(|player| !player.state.hand.cards.is_empty())
+                    .map(|player| Request {
+                        player: player.color,
+                        decision: Decision::ConfidenceFailure,
+                    })
+                    .collect_vec();
+
+                requests
+                    .into_iter()
+                    .for_each(|request| self.requests.push(request));
+            }
             DiscardEvent::FailureToImpress => {
                 let discarded_prizes = self
                     .players
@@ -46,22 +92,404 @@ impl Game {
         }
     }
 
-    fn apply_purchase_event(&mut self, event: PurchaseEvent) {
-        let current_player = &mut self.players[self.turn.player];
+    /// Destroy every army and tribe in a region. Armies go back to the supply
+    /// and tribes to their owners, and any player who lost tribes there is
+    /// overthrown.
+    fn riot(&mut self, region: Region, events: &mut Vec<GameEvent>) {
+        let armies = self.map.clear_armies(region);
 
-        match event {
-            PurchaseEvent::KohINoorRecovered => current_player.state.effects.kohinoor = true,
-            PurchaseEvent::Rumor => todo!(),
-            PurchaseEvent::OtherPersuasiveMethods => todo!(),
-            PurchaseEvent::PublicWithdrawal => todo!(),
-            PurchaseEvent::CourtlyManners => current_player.state.effects.courtly_manners = true,
-            PurchaseEvent::NationBuilding => current_player.state.effects.nation_building = true,
-            PurchaseEvent::PashtunwaliValues => todo!(),
-            PurchaseEvent::Nationalism => current_player.state.effects.nationalism = true,
-            PurchaseEvent::ConflictFatigue => self.effects.conflict_fatigue = true,
-            PurchaseEvent::NewTactics => current_player.state.effects.new_tactics = true,
-            PurchaseEvent::Rebuke => todo!(),
-            PurchaseEvent::PersianAristocracy => current_player.state.rupees.add(RupeeSet::new(3)),
+        events.extend(
+            armies
+                .counts()
+                .into_iter()
+                .filter(|&(_, count)| count > 0)
+                .map(|(coalition, count)| GameEvent::ArmiesRemoved {
+                    region,
+                    coalition,
+                    count,
+                }),
+        );
+        self.blocks.add(armies);
+
+        let mut tribes = self.map.clear_tribes(region);
+        let colors: Vec<Color> = self.players.iter().map(|player| player.color).collect();
+
+        for player in colors {
+            let removed = tribes.take(player);
+            let count = removed.count();
+
+            if count > 0 {
+                self.players[player].state.discard_cylinders(removed);
+                events.push(GameEvent::TribesRemoved {
+                    player,
+                    region,
+                    count,
+                });
+                self.overthrow(player, region, events);
+            }
         }
     }
+
+    /// Give a player an effect from an event card. The player doesn't have to
+    /// be the one whose turn it is.
+    fn apply_effect(&mut self, player: Color, effect: Effect, events: &mut Vec<GameEvent>) {
+        self.players[player].state.effects.apply(effect);
+        events.push(GameEvent::EffectApplied { player, effect });
+    }
+
+    /// Apply the effect of an event card bought by the current player. The
+    /// choice must already have been checked against the event.
+    pub(super) fn apply_purchase_event(
+        &mut self,
+        event: PurchaseEvent,
+        choice: Option<EventChoice>,
+        events: &mut Vec<GameEvent>,
+    ) {
+        let player = self.current_player().color;
+
+        match (event, choice) {
+            (PurchaseEvent::KohINoorRecovered, _) => {
+                self.apply_effect(player, Effect::KohINoor, events)
+            }
+            (PurchaseEvent::Rumor, Some(EventChoice::Rumor(target))) => {
+                self.apply_effect(target, Effect::Rumor, events)
+            }
+            (
+                PurchaseEvent::OtherPersuasiveMethods,
+                Some(EventChoice::OtherPersuasiveMethods(target)),
+            ) => {
+                let hand = mem::take(&mut self.players[player].state.hand);
+                let hand = mem::replace(&mut self.players[target].state.hand, hand);
+                self.players[player].state.hand = hand;
+
+                events.push(GameEvent::HandsExchanged {
+                    player,
+                    with: target,
+                });
+            }
+            (PurchaseEvent::CourtlyManners, _) => {
+                self.apply_effect(player, Effect::CourtlyManners, events)
+            }
+            (PurchaseEvent::NationBuilding, _) => {
+                self.apply_effect(player, Effect::NationBuilding, events)
+            }
+            (PurchaseEvent::PashtunwaliValues, Some(EventChoice::PashtunwaliValues(suit))) => {
+                // The climate is set even if an earlier Pashtunwali Values
+                // fixed it
+                if self.climate != suit {
+                    self.climate = suit;
+                    events.push(GameEvent::ClimateChanged(suit));
+                }
+
+                self.effects.pashtunwali_values = true;
+            }
+            (PurchaseEvent::Nationalism, _) => {
+                self.apply_effect(player, Effect::Nationalism, events)
+            }
+            (PurchaseEvent::ConflictFatigue, _) => self.effects.conflict_fatigue = true,
+            (PurchaseEvent::NewTactics, _) => self.apply_effect(player, Effect::NewTactics, events),
+            (PurchaseEvent::Rebuke, Some(EventChoice::Rebuke(region))) => self.riot(region, events),
+            (PurchaseEvent::PersianAristocracy, _) => {
+                self.players[player].state.rupees.add(RupeeSet::new(3))
+            }
+            (PurchaseEvent::PublicWithdrawal, _) => {
+                unreachable!("Public Withdrawal can't be bought")
+            }
+            (event, choice) => unreachable!("{:?} isn't a valid choice for {:?}", choice, event),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::iter;
+
+    use crate::{
+        deck::Deck,
+        game::{
+            test_util::{court_card, game_with, id, in_court, red_mut},
+            Action, ActionError, GameOver, Response, ResponseError,
+        },
+        market::{Column, Row},
+        primitives::{Coalition, Suit},
+    };
+
+    fn new_game() -> Game {
+        let mut game = game_with(&[Color::Red, Color::Blue], Coalition::Britain, 5);
+        game.deck = Deck::from_ids(["Money Lenders", "Mir Masjidi"].map(id)).unwrap();
+        game
+    }
+
+    /// Put a card in the leftmost slot of the top row, replacing whatever was
+    /// there
+    fn put_leftmost(game: &mut Game, name: &str) {
+        let row = game.market.get_row_mut(Row::Top);
+        row.take_card(Column::Zero);
+
+        let rest: Vec<_> = Column::all()
+            .filter_map(|column| row.take_card(column))
+            .map(|card| card.into_parts().0)
+            .collect();
+
+        row.fill_from(&mut iter::once(cards::Card::from_id(id(name))).chain(rest));
+    }
+
+    #[test]
+    fn test_cleanup_discards_event() {
+        let mut game = new_game();
+        put_leftmost(&mut game, "New Tactics");
+        game.market
+            .get_row_mut(Row::Top)
+            .get_card_mut(Column::Zero)
+            .unwrap()
+            .add_rupees(RupeeSet::new(2));
+
+        let events = game.cleanup_market();
+
+        assert_eq!(
+            events,
+            [
+                GameEvent::EventDiscarded {
+                    card: id("New Tactics"),
+                    rupees: 2,
+                },
+                GameEvent::ClimateChanged(Suit::Military),
+                GameEvent::CardDealt {
+                    row: Row::Top,
+                    column: Column::Five,
+                    card: id("Money Lenders"),
+                },
+            ]
+        );
+
+        assert_eq!(game.climate, Suit::Military);
+        assert_eq!(game.discard.last().unwrap().id(), id("New Tactics"));
+        assert_eq!(game.deck.len(), 1);
+        assert!(game.requests.is_empty());
+    }
+
+    #[test]
+    fn test_cleanup_respects_pashtunwali_values() {
+        let mut game = new_game();
+        game.effects.pashtunwali_values = true;
+        put_leftmost(&mut game, "New Tactics");
+
+        let events = game.cleanup_market();
+
+        assert_eq!(game.climate, Suit::Political);
+        assert!(!events.contains(&GameEvent::ClimateChanged(Suit::Military)));
+    }
+
+    #[test]
+    fn test_cleanup_queues_confidence_failure() {
+        let mut game = new_game();
+        put_leftmost(&mut game, "Other Persuasive Methods");
+
+        // Nobody has a card to discard
+        game.cleanup_market();
+        assert!(game.requests.is_empty());
+
+        put_leftmost(&mut game, "Other Persuasive Methods");
+        game.discard.clear();
+        red_mut(&mut game)
+            .hand
+            .cards
+            .push(court_card("Kabul Bazaar"));
+
+        game.cleanup_market();
+        assert_eq!(
+            game.requests.iter().collect_vec(),
+            [&Request {
+                player: Color::Red,
+                decision: Decision::ConfidenceFailure,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_cleanup_resolves_dominance_check() {
+        let mut game = new_game();
+        game.effects.conflict_fatigue = true;
+        put_leftmost(&mut game, "Dominance Check");
+
+        let events = game.cleanup_market();
+
+        assert_eq!(
+            events[..2],
+            [
+                GameEvent::EventDiscarded {
+                    card: id("Dominance Check"),
+                    rupees: 0,
+                },
+                GameEvent::DominanceCheck {
+                    card: id("Dominance Check"),
+                },
+            ]
+        );
+
+        // Dominance checks clear all effects
+        assert!(!game.effects.conflict_fatigue);
+        assert!(game.discard.last().unwrap().is_dominance());
+    }
+
+    #[test]
+    fn test_final_dominance_check_ends_the_game() {
+        let mut game = new_game();
+        game.discard
+            .extend((0..3).map(|_| cards::Card::from_id(id("Dominance Check"))));
+        game.players[Color::Blue].state.score = 5;
+        put_leftmost(&mut game, "Dominance Check");
+
+        let events = game.end_turn(Default::default()).unwrap();
+
+        assert!(events.contains(&GameEvent::DominanceCheck {
+            card: id("Dominance Check"),
+        }));
+        assert!(events.contains(&GameEvent::GameOver {
+            winner: Color::Blue
+        }));
+        assert!(!events
+            .iter()
+            .any(|event| matches!(event, GameEvent::TurnEnded { .. })));
+
+        // The turn doesn't pass, and nothing else can happen
+        let game_over = GameOver {
+            winner: Color::Blue,
+        };
+        assert_eq!(game.game_over, Some(game_over));
+        assert_eq!(game.turn.player, 0);
+        assert!(game.legal_actions().is_empty());
+        assert_eq!(
+            game.apply(Action::EndTurn(Default::default())),
+            Err(ActionError::GameOver(game_over))
+        );
+        assert_eq!(
+            game.respond(Color::Red, Response::Discard(0)),
+            Err(ResponseError::GameOver(game_over))
+        );
+    }
+
+    #[test]
+    fn test_riots() {
+        let mut game = new_game();
+        put_leftmost(&mut game, "Nation Building");
+
+        let armies = game.blocks.take_up_to(2, Coalition::Britain);
+        game.map.add_armies(Region::Kabul, armies);
+        let armies = game.blocks.take_up_to(1, Coalition::Russia);
+        game.map.add_armies(Region::Kabul, armies);
+
+        let red = red_mut(&mut game);
+        let tribes = red.bank.take_up_to(2);
+        red.court
+            .cards
+            .extend(["Murad Beg", "Kabul Bazaar"].map(in_court));
+        game.map.add_tribes(Region::Kabul, Color::Red, tribes);
+
+        // Blue has a spy on Murad Beg, and a Kabul political card but no
+        // tribes there
+        let blue = &mut game.players[Color::Blue].state;
+        let spy = blue.bank.take_up_to(1);
+        blue.court.cards.push_back(in_court("Shah Shujah Durrani"));
+        red_mut(&mut game).court.cards[0]
+            .spies
+            .add(Color::Blue, spy);
+
+        let events = game.cleanup_market();
+
+        assert_eq!(
+            events[1..5],
+            [
+                GameEvent::ArmiesRemoved {
+                    region: Region::Kabul,
+                    coalition: Coalition::Britain,
+                    count: 2,
+                },
+                GameEvent::ArmiesRemoved {
+                    region: Region::Kabul,
+                    coalition: Coalition::Russia,
+                    count: 1,
+                },
+                GameEvent::TribesRemoved {
+                    player: Color::Red,
+                    region: Region::Kabul,
+                    count: 2,
+                },
+                GameEvent::CardOverthrown {
+                    player: Color::Red,
+                    card: id("Murad Beg"),
+                },
+            ]
+        );
+
+        assert_eq!(game.map.total_block_counts()[Coalition::Britain], 0);
+        assert_eq!(game.blocks.count(Coalition::Britain), 12);
+        assert_eq!(game.map.total_tribe_counts()[Color::Red], 0);
+
+        // Every cylinder is back in its owner's bank
+        assert_eq!(game.players[Color::Red].state.bank.count(), 10);
+        assert_eq!(game.players[Color::Blue].state.bank.count(), 10);
+
+        assert_eq!(game.players[Color::Red].state.court.cards.len(), 1);
+        assert_eq!(game.players[Color::Blue].state.court.cards.len(), 1);
+        assert!(game.discard.iter().any(|card| card.id() == id("Murad Beg")));
+    }
+
+    #[test]
+    fn test_confidence_failure() {
+        let mut game = new_game();
+        put_leftmost(&mut game, "Other Persuasive Methods");
+
+        red_mut(&mut game)
+            .hand
+            .cards
+            .extend(["Kabul Bazaar", "Money Lenders"].map(court_card));
+        game.players[Color::Blue]
+            .state
+            .hand
+            .cards
+            .push(court_card("Murad Beg"));
+
+        assert_eq!(
+            game.respond(Color::Red, Response::Discard(0)),
+            Err(ResponseError::NoRequest(Color::Red))
+        );
+
+        game.cleanup_market();
+        assert_eq!(
+            game.requests.players_owing(Decision::ConfidenceFailure),
+            [Color::Red, Color::Blue]
+        );
+
+        // Each player answers for themselves, and their choice is checked
+        assert_eq!(
+            game.respond(Color::Blue, Response::Discard(1)),
+            Err(ResponseError::InvalidIndex(1))
+        );
+        assert_eq!(
+            game.respond(Color::Red, Response::Discard(1)).unwrap(),
+            [GameEvent::CardDiscarded {
+                player: Color::Red,
+                card: id("Money Lenders"),
+            }]
+        );
+        assert_eq!(
+            game.requests.players_owing(Decision::ConfidenceFailure),
+            [Color::Blue]
+        );
+        assert_eq!(
+            game.respond(Color::Blue, Response::Discard(0)).unwrap(),
+            [GameEvent::CardDiscarded {
+                player: Color::Blue,
+                card: id("Murad Beg"),
+            }]
+        );
+
+        assert!(game.requests.is_empty());
+        assert_eq!(red_mut(&mut game).hand.cards.len(), 1);
+        assert!(game.players[Color::Blue].state.hand.cards.is_empty());
+        assert_eq!(game.discard.last().unwrap().id(), id("Murad Beg"));
+    }
 }
@@ -0,0 +1,649 @@
+use std::{cmp, collections::HashSet, iter};
+
+use itertools::Itertools;
+use strum::IntoEnumIterator;
+
+use crate::{
+    cards::{
+        court::CardAction,
+        event::{self, PurchaseEvent},
+        Card,
+    },
+    map::Region,
+    market::{Column, Row},
+    player::Side,
+    primitives::Suit,
+};
+
+use super::{
+    movement::{move_piece, movement_steps},
+    Action, BattleSite, BattleTarget, BuildTarget, Discards, EventChoice, Game, Movement,
+    TaxSource,
+};
+
+impl Game {
+    /// List every legal action for the current player. Every action listed
+    /// will be accepted by [`Game::apply`].
+    pub fn legal_actions(&self) -> Vec<Action> {
+        let mut actions = Vec::new();
+
+        if self.game_over.is_some() {
+            return actions;
+        }
+
+        if self.check_actions_left().is_ok() {
+            actions.extend(self.purchase_candidates());
+            actions.extend(self.play_candidates());
+        }
+
+        actions.extend(self.card_action_candidates());
+        actions.extend(self.end_turn_candidates());
+
+        actions.retain(|action| self.validate(action).is_ok());
+        actions
+    }
+
+    fn purchase_candidates(&self) -> Vec<Action> {
+        [Row::Top, Row::Bottom]
+            .into_iter()
+            .cartesian_product(Column::all())
+            .flat_map(|(row, column)| {
+                let choices = self
+                    .market
+                    .get_card(row, column)
+                    .map_or_else(|| vec![None], |card| self.event_choices(card));
+
+                choices.into_iter().map(move |choice| Action::Purchase {
+                    row,
+                    column,
+                    choice,
+                })
+            })
+            .collect()
+    }
+
+    /// Every decision that could go with buying a card
+    fn event_choices(&self, card: &Card) -> Vec<Option<EventChoice>> {
+        let purchase = match *card {
+            Card::Event(event::Card::Event(ref event)) => event.purchase,
+            _ => return vec![None],
+        };
+
+        let color = self.current_player().color;
+        let opponents = self
+            .players
+            .iter()
+            .map(|player| player.color)
+            .filter(|&other| other != color);
+
+        let choices = match purchase {
+            PurchaseEvent::Rumor => opponents.map(EventChoice::Rumor).collect(),
+            PurchaseEvent::OtherPersuasiveMethods => {
+                opponents.map(EventChoice::OtherPersuasiveMethods).collect()
+            }
+            PurchaseEvent::PashtunwaliValues => [
+                Suit::Political,
+                Suit::Intelligence,
+                Suit::Economic,
+                Suit::Military,
+            ]
+            .map(EventChoice::PashtunwaliValues)
+            .to_vec(),
+            PurchaseEvent::Rebuke => Region::iter().map(EventChoice::Rebuke).collect(),
+            _ => return vec![None],
+        };
+
+        choices.into_iter().map(Some).collect()
+    }
+
+    fn play_candidates(&self) -> Vec<Action> {
+        let player = &self.current_player().state;
+
+        // Playing on either side of an empty court is the same thing
+        let sides: &[Side] = match player.court.cards.is_empty() {
+            true => &[Side::Right],
+            false => &[Side::Left, Side::Right],
+        };
+
+        player
+            .hand
+            .cards
+            .iter()
+            .flat_map(|card| {
+                let counts = self.play_counts(card);
+                let region = card.region;
+                let borders = self.map.borders_of(region);
+
+                let spy_targets = iter::once(card.id())
+                    .chain(
+                        self.players
+                            .iter()
+                            .flat_map(|player| &player.state.court.cards)
+                            .filter(|target| target.region == region)
+                            .map(|target| target.id()),
+                    )
+                    .collect_vec();
+
+                let road_choices = choose_with_replacement(&borders, counts.roads.max(0) as usize);
+                let spy_choices =
+                    choose_with_replacement(&spy_targets, counts.spies.max(0) as usize);
+
+                sides
+                    .iter()
+                    .cartesian_product(road_choices)
+                    .cartesian_product(spy_choices)
+                    .map(move |((&side, roads), spies)| Action::Play {
+                        card: card.id(),
+                        side,
+                        roads,
+                        spies,
+                    })
+            })
+            .collect()
+    }
+
+    fn card_action_candidates(&self) -> Vec<Action> {
+        let court = &self.current_player().state.court;
+        let color = self.current_player().color;
+
+        // Every rupee that could be taxed, one entry per rupee. Players who
+        // can't be taxed at all are filtered out later by validation.
+        let market_rupees = [Row::Top, Row::Bottom]
+            .into_iter()
+            .cartesian_product(Column::all())
+            .flat_map(|(row, column)| {
+                let rupees = self
+                    .market
+                    .get_card(row, column)
+                    .map_or(0, |card| card.rupees());
+                iter::repeat_n(TaxSource::Market(row, column), rupees.max(0) as usize)
+            });
+        let player_rupees = self
+            .players
+            .iter()
+            .filter(|player| player.color != color)
+            .flat_map(|player| {
+                let rupees = self.pricing().taxable_rupees(&player.state);
+                iter::repeat_n(TaxSource::Player(player.color), rupees as usize)
+            });
+        let taxable = market_rupees.chain(player_rupees).collect_vec();
+
+        // Every series of moves the best untapped Move card could make. Lower
+        // ranked cards make the shorter ones.
+        let most_moves = court
+            .cards
+            .iter()
+            .filter(|card| !card.tapped && card.actions.has(CardAction::Move))
+            .map(|card| card.rank.value() as usize)
+            .max()
+            .map_or_else(Vec::new, |rank| self.move_candidates(rank));
+
+        // Every site the current player could build on, and the most blocks
+        // they could build there
+        let build_sites = self.build_sites();
+        let most_builds = cmp::min(
+            self.pricing().max_builds(&self.current_player().state),
+            self.blocks
+                .count(self.current_player().state.loyalty)
+                .max(0) as usize,
+        );
+
+        // Every card the current player has a spy on
+        let spied_on = self
+            .players
+            .iter()
+            .flat_map(|player| &player.state.court.cards)
+            .filter(|card| card.spies.count(color) > 0)
+            .map(|card| card.id())
+            .collect_vec();
+
+        // Every site with pieces that could be battled, and the most that
+        // could be removed there
+        let battle_sites = self
+            .battle_sites()
+            .into_iter()
+            .map(|(site, targets)| (self.battle_strength(site), site, targets))
+            .collect_vec();
+
+        court
+            .cards
+            .iter()
+            .filter(|card| !card.tapped)
+            .flat_map(|card| {
+                let id = card.id();
+                let rank = card.rank.value() as usize;
+
+                let taxes = (1..=rank).flat_map(|count| {
+                    taxable
+                        .iter()
+                        .copied()
+                        .combinations(count)
+                        .unique()
+                        .map(move |sources| Action::Tax { card: id, sources })
+                });
+
+                let betrayals = spied_on.iter().cartesian_product([false, true]).map(
+                    move |(&target, take_prize)| Action::Betray {
+                        card: id,
+                        target,
+                        take_prize,
+                    },
+                );
+
+                let builds = match card.actions.has(CardAction::Build) {
+                    true => (1..=most_builds)
+                        .flat_map(|count| choose_with_replacement(&build_sites, count))
+                        .collect_vec(),
+                    false => Vec::new(),
+                };
+                let builds = builds
+                    .into_iter()
+                    .map(move |targets| Action::Build { card: id, targets });
+
+                let moves = most_moves
+                    .iter()
+                    .filter(move |moves| card.actions.has(CardAction::Move) && moves.len() <= rank)
+                    .map(move |moves| Action::Move {
+                        card: id,
+                        moves: moves.clone(),
+                    });
+
+                let battles = match card.actions.has(CardAction::Battle) {
+                    true => battle_sites
+                        .iter()
+                        .flat_map(|(strength, site, targets)| {
+                            (1..=cmp::min(rank, *strength)).flat_map(move |count| {
+                                choose_with_replacement(targets, count).into_iter().map(
+                                    move |targets| Action::Battle {
+                                        card: id,
+                                        site: *site,
+                                        targets,
+                                    },
+                                )
+                            })
+                        })
+                        .collect_vec(),
+                    false => Vec::new(),
+                };
+
+                taxes
+                    .chain(iter::once(Action::Gift { card: id }))
+                    .chain(builds)
+                    .chain(moves)
+                    .chain(betrayals)
+                    .chain(battles)
+                    .collect_vec()
+            })
+            .collect()
+    }
+
+    /// Every army and road site next to a region the current player rules
+    fn build_sites(&self) -> Vec<BuildTarget> {
+        let color = self.current_player().color;
+        let ruled = Region::iter()
+            .filter(|&region| self.map.ruler(region, &self.players) == Some(color))
+            .collect_vec();
+
+        let armies = ruled.iter().map(|&region| BuildTarget::Army(region));
+        let roads = ruled
+            .iter()
+            .flat_map(|&region| self.map.borders_of(region))
+            .sorted()
+            .dedup()
+            .map(BuildTarget::Road);
+
+        armies.chain(roads).collect()
+    }
+
+    /// Every site a battle could happen at, with each kind of piece there.
+    /// Friendly and protected pieces are filtered out later by validation.
+    fn battle_sites(&self) -> Vec<(BattleSite, Vec<BattleTarget>)> {
+        let colors = self.players.iter().map(|player| player.color).collect_vec();
+
+        let regions = Region::iter().map(|region| {
+            let armies = self
+                .map
+                .armies(region)
+                .counts()
+                .into_iter()
+                .filter(|&(_, count)| count > 0)
+                .map(|(coalition, _)| BattleTarget::Army(coalition));
+            let roads = self.map.borders_of(region).into_iter().flat_map(|border| {
+                self.map
+                    .roads_on(border)
+                    .map(|roads| roads.counts())
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter(|&(_, count)| count > 0)
+                    .map(move |(coalition, _)| BattleTarget::Road(border, coalition))
+            });
+            let tribes = colors
+                .iter()
+                .filter(|&&player| self.map.tribes(region).count(player) > 0)
+                .map(|&player| BattleTarget::Tribe(player));
+
+            let targets = armies.chain(roads).chain(tribes).collect();
+            (BattleSite::Region(region), targets)
+        });
+
+        let cards = self
+            .players
+            .iter()
+            .flat_map(|player| &player.state.court.cards)
+            .map(|card| {
+                let targets = colors
+                    .iter()
+                    .filter(|&&player| card.spies.count(player) > 0)
+                    .map(|&player| BattleTarget::Spy(player))
+                    .collect();
+                (BattleSite::Card(card.id()), targets)
+            });
+
+        regions
+            .chain(cards)
+            .filter(|(_, targets): &(_, Vec<_>)| !targets.is_empty())
+            .collect()
+    }
+
+    /// Every series of one to `count` moves the current player could make,
+    /// keeping only the shortest series found for each resulting position
+    fn move_candidates(&self, count: usize) -> Vec<Vec<Movement>> {
+        let mover = self.mover();
+        let mut candidates = Vec::new();
+        let mut partial = vec![(Vec::new(), self.pieces())];
+
+        // Many orders of moves end up with the same pieces in the same
+        // places, so only the first sequence to reach each position is kept
+        let mut reached = HashSet::new();
+
+        for _ in 0..count {
+            let mut next = Vec::new();
+
+            for (moves, pieces) in &partial {
+                for step in movement_steps(pieces, mover) {
+                    let mut after = pieces.clone();
+                    move_piece(&mut after, mover, step).expect("steps are valid");
+
+                    if reached.insert(after.clone()) {
+                        let mut moves = moves.clone();
+                        moves.push(step);
+                        next.push((moves, after));
+                    }
+                }
+            }
+
+            partial = next;
+            candidates.extend(partial.iter().map(|(moves, _)| moves.clone()));
+        }
+
+        candidates
+    }
+
+    fn end_turn_candidates(&self) -> Vec<Action> {
+        let player = &self.current_player().state;
+        let court_choices = (0..player.court.cards.len()).combinations(player.court_overflow());
+
+        court_choices
+            .flat_map(|court| {
+                let court: HashSet<usize> = court.into_iter().collect();
+                let hand_discards = self.hand_discards_needed(&court);
+
+                (0..player.hand.cards.len())
+                    .combinations(hand_discards)
+                    .map(move |hand| {
+                        Action::EndTurn(Discards {
+                            court: court.clone(),
+                            hand: hand.into_iter().collect(),
+                        })
+                    })
+            })
+            .collect()
+    }
+}
+
+/// Every way of choosing `count` items, allowing repeats and ignoring order
+fn choose_with_replacement<T: Copy>(items: &[T], count: usize) -> Vec<Vec<T>> {
+    match count {
+        0 => vec![Vec::new()],
+        _ => items
+            .iter()
+            .copied()
+            .combinations_with_replacement(count)
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        cards::CardId,
+        game::{
+            test_util::{court_card, game_with, in_court, red_mut},
+            ActionError,
+        },
+        map::{Border, Herat, Kabul, Punjab, Transcaspia},
+        player::Color,
+        primitives::{Coalition, Suit},
+        rupees::RupeeSet,
+    };
+
+    fn new_game() -> Game {
+        let mut game = game_with(&[Color::Red, Color::Blue], Coalition::Russia, 9);
+        game.climate = Suit::Economic;
+        game
+    }
+
+    /// Games aren't `Clone`, but they can be copied through their serialized
+    /// form
+    fn copy(game: &Game) -> Game {
+        serde_json::from_value(serde_json::to_value(game).unwrap()).unwrap()
+    }
+
+    fn assert_all_apply(game: &Game) {
+        for action in game.legal_actions() {
+            if let Err(err) = copy(game).apply(action.clone()) {
+                panic!("legal action {:?} was rejected: {}", action, err);
+            }
+        }
+    }
+
+    fn count(actions: &[Action], pred: impl Fn(&Action) -> bool) -> usize {
+        actions.iter().filter(|&action| pred(action)).count()
+    }
+
+    #[test]
+    fn test_opening_actions() {
+        let game = new_game();
+        let actions = game.legal_actions();
+
+        // 4 rupees buys anything in the first five columns
+        assert_eq!(
+            count(&actions, |action| matches!(action, Action::Purchase { .. })),
+            10
+        );
+        assert_eq!(
+            count(&actions, |action| matches!(action, Action::EndTurn(_))),
+            1
+        );
+        assert_eq!(actions.len(), 11);
+        assert_all_apply(&game);
+    }
+
+    #[test]
+    fn test_marked_cards_are_not_for_sale() {
+        let mut game = new_game();
+        game.apply(Action::Purchase {
+            row: Row::Top,
+            column: Column::Two,
+            choice: None,
+        })
+        .unwrap();
+
+        let actions = game.legal_actions();
+        assert!(!actions.contains(&Action::Purchase {
+            row: Row::Top,
+            column: Column::Zero,
+            choice: None,
+        }));
+        assert!(actions.contains(&Action::Purchase {
+            row: Row::Bottom,
+            column: Column::Zero,
+            choice: None,
+        }));
+    }
+
+    #[test]
+    fn test_plays_and_card_actions() {
+        let mut game = new_game();
+        let red = red_mut(&mut game);
+        red.hand.cards.push(court_card("Money Lenders"));
+        red.hand.cards.push(court_card("Alexander Burnes"));
+        red.court.cards.push_back(in_court("Kabul Bazaar"));
+        red.rupees.add(RupeeSet::new(2));
+
+        game.market
+            .get_row_mut(Row::Bottom)
+            .get_card_mut(Column::Three)
+            .unwrap()
+            .add_rupees(RupeeSet::new(1));
+
+        let actions = game.legal_actions();
+
+        // Money Lenders places two roads on any of Kabul's four borders, on
+        // either side of the court
+        assert_eq!(
+            count(&actions, |action| matches!(
+                action,
+                Action::Play { card, .. } if card.name() == "Money Lenders"
+            )),
+            2 * 10
+        );
+
+        // Alexander Burnes places two spies, on itself or Kabul Bazaar
+        assert_eq!(
+            count(&actions, |action| matches!(
+                action,
+                Action::Play { card, .. } if card.name() == "Alexander Burnes"
+            )),
+            2 * 3
+        );
+
+        // Kabul Bazaar can tax the one rupee in the market, and buy a gift
+        assert_eq!(
+            count(&actions, |action| matches!(action, Action::Tax { .. })),
+            1
+        );
+        assert_eq!(
+            count(&actions, |action| matches!(action, Action::Gift { .. })),
+            1
+        );
+
+        assert_all_apply(&game);
+    }
+
+    #[test]
+    fn test_only_bonus_actions_when_out_of_actions() {
+        let mut game = new_game();
+        red_mut(&mut game)
+            .court
+            .cards
+            .extend(["Kabul Bazaar", "Alexander Burnes"].map(in_court));
+        game.turn.actions_taken = 2;
+
+        // Kabul Bazaar is economic, and so favored
+        let actions = game.legal_actions();
+        assert_eq!(
+            actions,
+            [
+                Action::Gift {
+                    card: CardId::from_name("Kabul Bazaar").unwrap(),
+                },
+                Action::EndTurn(Discards::default()),
+            ]
+        );
+        assert_all_apply(&game);
+
+        // Taxing nothing isn't an action at all
+        assert_eq!(
+            game.validate(&Action::Tax {
+                card: CardId::from_name("Kabul Bazaar").unwrap(),
+                sources: vec![],
+            }),
+            Err(ActionError::NothingToDo(CardAction::Tax))
+        );
+    }
+
+    #[test]
+    fn test_end_turn_discards() {
+        let mut game = new_game();
+        red_mut(&mut game).hand.cards.extend(
+            [
+                "Money Lenders",
+                "Alexander Burnes",
+                "Kabul Bazaar",
+                "Mir Masjidi",
+            ]
+            .map(court_card),
+        );
+        game.turn.actions_taken = 2;
+
+        // The hand limit is 2, so any 2 of the 4 cards must go
+        let actions = game.legal_actions();
+        assert_eq!(actions.len(), 6);
+        assert!(actions.iter().all(|action| matches!(
+            action,
+            Action::EndTurn(discards) if discards.hand.len() == 2 && discards.court.is_empty()
+        )));
+    }
+
+    #[test]
+    fn test_move_candidates_on_a_busy_board() {
+        let mut game = new_game();
+        red_mut(&mut game)
+            .court
+            .cards
+            .push_back(in_court("Hari Singh Nalwa"));
+
+        for (region, count) in [(Herat, 2), (Kabul, 2), (Transcaspia, 1)] {
+            let armies = game.blocks.take_up_to(count, Coalition::Russia);
+            game.map.add_armies(region, armies);
+        }
+        for (from, to) in [
+            (Herat, Kabul),
+            (Kabul, Transcaspia),
+            (Herat, Transcaspia),
+            (Kabul, Punjab),
+        ] {
+            let road = game.blocks.take_up_to(1, Coalition::Russia);
+            game.map.add_roads(Border::new(from, to), road).unwrap();
+        }
+
+        for name in ["Money Lenders", "Kabul Bazaar"] {
+            let mut card = in_court(name);
+            card.spies
+                .add(Color::Red, red_mut(&mut game).bank.take_up_to(1));
+            game.players[Color::Blue].state.court.cards.push_back(card);
+        }
+
+        let candidates = game.move_candidates(3);
+
+        // Each candidate leaves the pieces somewhere different
+        let mover = game.mover();
+        let positions: HashSet<_> = candidates
+            .iter()
+            .map(|moves| {
+                let mut pieces = game.pieces();
+                for &step in moves {
+                    move_piece(&mut pieces, mover, step).unwrap();
+                }
+                pieces
+            })
+            .collect();
+        assert_eq!(positions.len(), candidates.len());
+
+        // Every order of up to three steps would be over 20,000 sequences
+        assert!(candidates.len() < 3000);
+    }
+}
@@ -0,0 +1,769 @@
+use strum::IntoEnumIterator;
+
+use crate::{
+    cards::{
+        court::{CardAction, SpecialAbility},
+        CardId,
+    },
+    map::{Border, Map, Region},
+    player::Color,
+    primitives::{Coalition, Suit},
+};
+
+use super::{action::CardUse, ActionError, Game, GameEvent, Movement};
+
+/// What the current player is allowed to move, and how
+#[derive(Debug, Clone, Copy)]
+pub(super) struct Mover {
+    player: Color,
+    coalition: Coalition,
+
+    /// Armies may move without a road, from Indian Supplies
+    ignore_roads: bool,
+
+    /// Tribes may move, from Nationalism
+    move_tribes: bool,
+
+    /// Cards sharing a region are adjacent for spies, from Strange
+    /// Bedfellows
+    strange_bedfellows: bool,
+
+    /// Spies may move two cards at a time, from Well Connected
+    well_connected: bool,
+}
+
+/// A court card that spies can move onto
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct SpyCard {
+    id: CardId,
+    region: Region,
+    owner: Color,
+    political: bool,
+
+    /// How many of the mover's spies are on the card
+    spies: i8,
+}
+
+/// Every court card around the table, in turn order and from left to right
+/// within each court. Spies move between neighboring cards, and the last
+/// card is next to the first.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(super) struct SpyRing {
+    cards: Vec<SpyCard>,
+}
+
+impl SpyRing {
+    fn position(&self, card: CardId) -> Result<usize, ActionError> {
+        self.cards
+            .iter()
+            .position(|spy_card| spy_card.id == card)
+            .ok_or(ActionError::NotInAnyCourt(card))
+    }
+
+    /// The cards next to a card, by position
+    fn neighbors(&self, index: usize, mover: Mover) -> Vec<usize> {
+        let count = self.cards.len();
+        let region = self.cards[index].region;
+
+        let mut neighbors = vec![(index + 1) % count, (index + count - 1) % count];
+        if mover.strange_bedfellows {
+            neighbors.extend((0..count).filter(|&other| self.cards[other].region == region));
+        }
+
+        neighbors.retain(|&other| other != index);
+        neighbors
+    }
+
+    /// The cards a single spy can reach in one step, by position
+    fn reachable(&self, index: usize, mover: Mover) -> Vec<usize> {
+        let mut reachable = self.neighbors(index, mover);
+
+        if mover.well_connected {
+            let further = reachable
+                .iter()
+                .flat_map(|&next| self.neighbors(next, mover))
+                .collect::<Vec<_>>();
+            reachable.extend(further);
+        }
+
+        reachable.sort_unstable();
+        reachable.dedup();
+        reachable.retain(|&other| other != index);
+        reachable
+    }
+
+    /// Remove a player's political cards in a region, after they've been
+    /// overthrown there. Any spies on them go back to their owners.
+    fn overthrow(&mut self, player: Color, region: Region) {
+        self.cards
+            .retain(|card| !(card.owner == player && card.political && card.region == region));
+    }
+}
+
+/// The pieces a series of moves is played out on
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(super) struct Pieces {
+    pub map: Map,
+    pub spies: SpyRing,
+}
+
+/// The region two different borders both touch, if any
+fn shared_region(from: Border, to: Border) -> Option<Region> {
+    [from.front(), from.back()]
+        .into_iter()
+        .find(|&region| from != to && to.borders(region))
+}
+
+/// Check that a piece can cross the border between two regions, which
+/// needs a road of the given coalition unless roads are being ignored
+fn check_crossing(
+    map: &Map,
+    from: Region,
+    to: Region,
+    coalition: Coalition,
+    ignore_roads: bool,
+) -> Result<(), ActionError> {
+    let border = map.border(from, to)?;
+
+    match ignore_roads || map.roads_on(border)?.count(coalition) > 0 {
+        true => Ok(()),
+        false => Err(ActionError::NoRoad { border, coalition }),
+    }
+}
+
+/// Move a single piece one step on a map, returning what moved. If the move
+/// isn't allowed, the map is left as it was.
+pub(super) fn move_piece(
+    pieces: &mut Pieces,
+    mover: Mover,
+    movement: Movement,
+) -> Result<GameEvent, ActionError> {
+    let coalition = mover.coalition;
+    let map = &mut pieces.map;
+
+    match movement {
+        Movement::Army { from, to } => {
+            check_crossing(map, from, to, coalition, mover.ignore_roads)?;
+
+            let army = map.take_armies(from, 1, coalition);
+            if army.count(coalition) == 0 {
+                return Err(ActionError::NothingToMove(movement));
+            }
+
+            map.add_armies(to, army);
+            Ok(GameEvent::ArmyMoved {
+                from,
+                to,
+                coalition,
+            })
+        }
+        Movement::Road { from, to } => {
+            map.roads_on(from)?;
+            map.roads_on(to)?;
+
+            if shared_region(from, to).is_none() {
+                return Err(ActionError::InvalidRoadMove { from, to });
+            }
+
+            let road = map.remove_roads(from, 1, coalition)?;
+            if road.count(coalition) == 0 {
+                return Err(ActionError::NothingToMove(movement));
+            }
+
+            map.add_roads(to, road).expect("both borders were checked");
+            Ok(GameEvent::RoadMoved {
+                from,
+                to,
+                coalition,
+            })
+        }
+        Movement::Tribe { from, to } => {
+            if !mover.move_tribes {
+                return Err(ActionError::TribesCannotMove);
+            }
+
+            // Tribes move like armies of their owner's coalition
+            check_crossing(map, from, to, coalition, false)?;
+
+            let tribe = map.remove_tribes(from, mover.player, 1);
+            if tribe.count() == 0 {
+                return Err(ActionError::NothingToMove(movement));
+            }
+
+            map.add_tribes(to, mover.player, tribe);
+            if map.tribes(from).count(mover.player) == 0 {
+                pieces.spies.overthrow(mover.player, from);
+            }
+
+            Ok(GameEvent::TribeMoved {
+                player: mover.player,
+                from,
+                to,
+            })
+        }
+        Movement::Spy { from, to } => {
+            let ring = &mut pieces.spies;
+            let from_index = ring.position(from)?;
+            let to_index = ring.position(to)?;
+
+            if !ring.reachable(from_index, mover).contains(&to_index) {
+                return Err(ActionError::InvalidSpyMove { from, to });
+            }
+            if ring.cards[from_index].spies == 0 {
+                return Err(ActionError::NothingToMove(movement));
+            }
+
+            ring.cards[from_index].spies -= 1;
+            ring.cards[to_index].spies += 1;
+            Ok(GameEvent::SpyMoved {
+                player: mover.player,
+                from,
+                to,
+            })
+        }
+    }
+}
+
+/// Every single step the mover could take
+pub(super) fn movement_steps(pieces: &Pieces, mover: Mover) -> Vec<Movement> {
+    let coalition = mover.coalition;
+    let map = &pieces.map;
+    let mut steps = Vec::new();
+
+    for from in Region::iter() {
+        for to in map.neighbors(from) {
+            let army_crossing = check_crossing(map, from, to, coalition, mover.ignore_roads);
+            if map.armies(from).count(coalition) > 0 && army_crossing.is_ok() {
+                steps.push(Movement::Army { from, to });
+            }
+
+            let tribe_crossing = check_crossing(map, from, to, coalition, false);
+            if mover.move_tribes
+                && map.tribes(from).count(mover.player) > 0
+                && tribe_crossing.is_ok()
+            {
+                steps.push(Movement::Tribe { from, to });
+            }
+        }
+    }
+
+    for (from, roads) in map.borders() {
+        if roads.count(coalition) == 0 {
+            continue;
+        }
+
+        steps.extend(
+            map.borders()
+                .map(|(to, _)| to)
+                .filter(|&to| shared_region(from, to).is_some())
+                .map(|to| Movement::Road { from, to }),
+        );
+    }
+
+    let ring = &pieces.spies;
+    for (index, card) in ring.cards.iter().enumerate() {
+        if card.spies == 0 {
+            continue;
+        }
+
+        steps.extend(
+            ring.reachable(index, mover)
+                .into_iter()
+                .map(|other| Movement::Spy {
+                    from: card.id,
+                    to: ring.cards[other].id,
+                }),
+        );
+    }
+
+    steps
+}
+
+impl Game {
+    /// What the current player can move
+    pub(super) fn mover(&self) -> Mover {
+        let player = self.current_player();
+        let has_ability = |ability| {
+            player
+                .state
+                .court
+                .cards
+                .iter()
+                .any(|card| card.ability == Some(ability))
+        };
+
+        Mover {
+            player: player.color,
+            coalition: player.state.loyalty,
+            ignore_roads: has_ability(SpecialAbility::IndianSupplies),
+            move_tribes: player.state.effects.nationalism,
+            strange_bedfellows: has_ability(SpecialAbility::StrangeBedfellows),
+            well_connected: has_ability(SpecialAbility::WellConnected),
+        }
+    }
+
+    /// Every piece on the table that the current player could move
+    pub(super) fn pieces(&self) -> Pieces {
+        let color = self.current_player().color;
+
+        let cards = self
+            .players
+            .iter()
+            .flat_map(|player| {
+                player.state.court.cards.iter().map(|card| SpyCard {
+                    id: card.id(),
+                    region: card.region,
+                    owner: player.color,
+                    political: card.suit == Suit::Political,
+                    spies: card.spies.count(color),
+                })
+            })
+            .collect();
+
+        Pieces {
+            map: self.map.clone(),
+            spies: SpyRing { cards },
+        }
+    }
+
+    /// Move one of a player's spies from one court card to another
+    fn transfer_spy(&mut self, player: Color, from: CardId, to: CardId) {
+        let owner = self.court_owner(from).expect("spy move was checked");
+        let spy = self.players[owner]
+            .state
+            .court
+            .get_mut(from)
+            .expect("owner was just found")
+            .spies
+            .take_up_to(player, 1);
+
+        let owner = self.court_owner(to).expect("spy move was checked");
+        self.players[owner]
+            .state
+            .court
+            .get_mut(to)
+            .expect("owner was just found")
+            .spies
+            .add(player, spy);
+    }
+
+    /// Check that the current player can make a series of moves with a court
+    /// card. Each move takes a single piece one step, and a card makes as many
+    /// moves as its rank. The moves are made in order, so a piece can take
+    /// several steps by being moved more than once.
+    pub(super) fn check_move(
+        &self,
+        card: CardId,
+        moves: &[Movement],
+    ) -> Result<CardUse, ActionError> {
+        let card_use = self.check_card_action(card, CardAction::Move)?;
+        if moves.is_empty() {
+            return Err(ActionError::NothingToDo(CardAction::Move));
+        }
+        let rank = self
+            .current_player()
+            .state
+            .court
+            .get(card)
+            .map_or(0, |card| card.rank.value());
+
+        if moves.len() > rank as usize {
+            return Err(ActionError::TooManyMoves {
+                requested: moves.len(),
+                rank,
+            });
+        }
+
+        let mover = self.mover();
+        let mut pieces = self.pieces();
+
+        for &movement in moves {
+            move_piece(&mut pieces, mover, movement)?;
+        }
+
+        Ok(card_use)
+    }
+
+    pub(super) fn move_pieces(
+        &mut self,
+        card: CardId,
+        moves: &[Movement],
+    ) -> Result<Vec<GameEvent>, ActionError> {
+        let card_use = self.check_move(card, moves)?;
+
+        let mut events = Vec::new();
+        self.use_card(card, card_use, &mut events);
+
+        let mover = self.mover();
+        let mut pieces = self.pieces();
+
+        for &movement in moves {
+            let moved = move_piece(&mut pieces, mover, movement).expect("moves were checked");
+            events.push(moved);
+            self.map.clone_from(&pieces.map);
+
+            match movement {
+                // Moving a player's last tribe out of a region overthrows
+                // them, which can change the map and courts again
+                Movement::Tribe { from, .. } => {
+                    if self.map.tribes(from).count(mover.player) == 0 {
+                        self.overthrow(mover.player, from, &mut events);
+                        pieces = self.pieces();
+                    }
+                }
+                Movement::Spy { from, to } => self.transfer_spy(mover.player, from, to),
+                Movement::Army { .. } | Movement::Road { .. } => {}
+            }
+        }
+
+        Ok(events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        blocks::BlockSet,
+        game::{
+            test_util::{id, in_court, new_game, red_mut},
+            Action, ActionError,
+        },
+        map::{BorderError, Herat, Kabul, Kandahar, Persia, Punjab},
+    };
+
+    fn add_armies(game: &mut Game, region: Region, count: i8) {
+        let armies = game.blocks.take_up_to(count, Coalition::Britain);
+        game.map.add_armies(region, armies);
+    }
+
+    fn add_road(game: &mut Game, from: Region, to: Region) {
+        let road = game.blocks.take_up_to(1, Coalition::Britain);
+        game.map.add_roads(Border::new(from, to), road).unwrap();
+    }
+
+    fn british(blocks: BlockSet) -> i8 {
+        blocks.count(Coalition::Britain)
+    }
+
+    fn move_with(card: &str, moves: Vec<Movement>) -> Action {
+        Action::Move {
+            card: id(card),
+            moves,
+        }
+    }
+
+    fn army(from: Region, to: Region) -> Movement {
+        Movement::Army { from, to }
+    }
+
+    #[test]
+    fn test_armies_follow_roads() {
+        let mut game = new_game();
+        red_mut(&mut game)
+            .court
+            .cards
+            .push_back(in_court("Hari Singh Nalwa"));
+        add_armies(&mut game, Punjab, 2);
+        add_road(&mut game, Kabul, Punjab);
+
+        assert_eq!(
+            game.validate(&move_with("Hari Singh Nalwa", vec![army(Punjab, Kandahar)])),
+            Err(ActionError::NoRoad {
+                border: Border::new(Kandahar, Punjab),
+                coalition: Coalition::Britain,
+            })
+        );
+        assert_eq!(
+            game.validate(&move_with("Hari Singh Nalwa", vec![army(Punjab, Persia)])),
+            Err(ActionError::Border(BorderError::NotAdjacent(
+                Persia, Punjab
+            )))
+        );
+        assert_eq!(
+            game.validate(&move_with("Hari Singh Nalwa", vec![army(Kabul, Punjab)])),
+            Err(ActionError::NothingToMove(army(Kabul, Punjab)))
+        );
+        assert_eq!(
+            game.validate(&move_with("Hari Singh Nalwa", vec![army(Punjab, Kabul); 4])),
+            Err(ActionError::TooManyMoves {
+                requested: 4,
+                rank: 3,
+            })
+        );
+
+        // The second step needs a road out of Kabul too
+        let steps = vec![army(Punjab, Kabul), army(Kabul, Herat)];
+        assert_eq!(
+            game.validate(&move_with("Hari Singh Nalwa", steps.clone())),
+            Err(ActionError::NoRoad {
+                border: Border::new(Herat, Kabul),
+                coalition: Coalition::Britain,
+            })
+        );
+
+        add_road(&mut game, Herat, Kabul);
+        let events = game.apply(move_with("Hari Singh Nalwa", steps)).unwrap();
+
+        assert_eq!(
+            events,
+            [
+                GameEvent::ArmyMoved {
+                    from: Punjab,
+                    to: Kabul,
+                    coalition: Coalition::Britain,
+                },
+                GameEvent::ArmyMoved {
+                    from: Kabul,
+                    to: Herat,
+                    coalition: Coalition::Britain,
+                },
+            ]
+        );
+        assert_eq!(british(game.map.armies(Punjab)), 1);
+        assert_eq!(british(game.map.armies(Kabul)), 0);
+        assert_eq!(british(game.map.armies(Herat)), 1);
+        assert!(game.players[Color::Red].state.court.cards[0].tapped);
+    }
+
+    #[test]
+    fn test_indian_supplies() {
+        let mut game = new_game();
+        red_mut(&mut game)
+            .court
+            .cards
+            .push_back(in_court("Company Commissariat"));
+        add_armies(&mut game, Punjab, 1);
+
+        game.apply(move_with(
+            "Company Commissariat",
+            vec![army(Punjab, Kandahar), army(Kandahar, Herat)],
+        ))
+        .unwrap();
+        assert_eq!(british(game.map.armies(Herat)), 1);
+    }
+
+    #[test]
+    fn test_move_roads() {
+        let mut game = new_game();
+        red_mut(&mut game)
+            .court
+            .cards
+            .push_back(in_court("Hari Singh Nalwa"));
+        add_road(&mut game, Kabul, Punjab);
+
+        let from = Border::new(Kabul, Punjab);
+        let far = Border::new(Persia, Herat);
+        assert_eq!(
+            game.validate(&move_with(
+                "Hari Singh Nalwa",
+                vec![Movement::Road { from, to: far }]
+            )),
+            Err(ActionError::InvalidRoadMove { from, to: far })
+        );
+
+        // Two steps takes the road from Punjab, through Kabul, to Herat
+        let steps = vec![
+            Movement::Road {
+                from,
+                to: Border::new(Kabul, Kandahar),
+            },
+            Movement::Road {
+                from: Border::new(Kabul, Kandahar),
+                to: Border::new(Herat, Kandahar),
+            },
+        ];
+        game.apply(move_with("Hari Singh Nalwa", steps)).unwrap();
+
+        assert_eq!(british(game.map.roads_on(from).unwrap()), 0);
+        assert_eq!(
+            british(game.map.roads_on(Border::new(Herat, Kandahar)).unwrap()),
+            1
+        );
+    }
+
+    #[test]
+    fn test_nationalism_moves_tribes() {
+        let mut game = new_game();
+        let red = red_mut(&mut game);
+        red.court
+            .cards
+            .extend([in_court("Hari Singh Nalwa"), in_court("Murad Beg")]);
+        game.place_tribe(Color::Red, Kabul).unwrap();
+        add_road(&mut game, Kabul, Punjab);
+
+        let tribe = vec![Movement::Tribe {
+            from: Kabul,
+            to: Punjab,
+        }];
+        assert_eq!(
+            game.validate(&move_with("Hari Singh Nalwa", tribe.clone())),
+            Err(ActionError::TribesCannotMove)
+        );
+
+        red_mut(&mut game).effects.nationalism = true;
+        let events = game.apply(move_with("Hari Singh Nalwa", tribe)).unwrap();
+
+        // Red left their last tribe in Kabul, and lost Murad Beg with it
+        assert_eq!(
+            events,
+            [
+                GameEvent::TribeMoved {
+                    player: Color::Red,
+                    from: Kabul,
+                    to: Punjab,
+                },
+                GameEvent::CardOverthrown {
+                    player: Color::Red,
+                    card: id("Murad Beg"),
+                },
+            ]
+        );
+        assert_eq!(game.map.tribes(Punjab).count(Color::Red), 1);
+    }
+
+    fn spy(from: &str, to: &str) -> Movement {
+        Movement::Spy {
+            from: id(from),
+            to: id(to),
+        }
+    }
+
+    /// A game where red has a single card, and a spy on the first of blue's
+    /// cards from Kabul, Herat, Punjab, and Kabul again
+    fn spy_game(card: &str) -> Game {
+        let mut game = new_game();
+        red_mut(&mut game).court.cards.push_back(in_court(card));
+
+        let mut target = in_court("Murad Beg");
+        target
+            .spies
+            .add(Color::Red, red_mut(&mut game).bank.take_up_to(1));
+        game.players[Color::Blue].state.court.cards.extend([
+            target,
+            in_court("Herat Guild of Couriers"),
+            in_court("Claude Wade"),
+            in_court("Charles Masson"),
+        ]);
+        game
+    }
+
+    #[test]
+    fn test_move_spies() {
+        let mut game = spy_game("Alexander Burnes");
+        let burnes = |moves| move_with("Alexander Burnes", moves);
+
+        assert_eq!(
+            game.validate(&burnes(vec![spy("Murad Beg", "Claude Wade")])),
+            Err(ActionError::InvalidSpyMove {
+                from: id("Murad Beg"),
+                to: id("Claude Wade"),
+            })
+        );
+        assert_eq!(
+            game.validate(&burnes(vec![spy("Charles Masson", "Claude Wade")])),
+            Err(ActionError::NothingToMove(spy(
+                "Charles Masson",
+                "Claude Wade"
+            )))
+        );
+        assert_eq!(
+            game.validate(&burnes(vec![spy("Murad Beg", "Dost Mohammad")])),
+            Err(ActionError::NotInAnyCourt(id("Dost Mohammad")))
+        );
+        assert!(game.legal_actions().contains(&burnes(vec![
+            spy("Murad Beg", "Herat Guild of Couriers"),
+            spy("Herat Guild of Couriers", "Claude Wade"),
+        ])));
+
+        // The ring of cards wraps around from red's court to the end of
+        // blue's
+        let steps = vec![
+            spy("Murad Beg", "Alexander Burnes"),
+            spy("Alexander Burnes", "Charles Masson"),
+        ];
+        let events = game.apply(burnes(steps)).unwrap();
+
+        assert_eq!(
+            events,
+            [
+                GameEvent::SpyMoved {
+                    player: Color::Red,
+                    from: id("Murad Beg"),
+                    to: id("Alexander Burnes"),
+                },
+                GameEvent::SpyMoved {
+                    player: Color::Red,
+                    from: id("Alexander Burnes"),
+                    to: id("Charles Masson"),
+                },
+            ]
+        );
+
+        let court = &game.players[Color::Blue].state.court;
+        assert_eq!(
+            court.get(id("Murad Beg")).unwrap().spies.count(Color::Red),
+            0
+        );
+        assert_eq!(
+            court
+                .get(id("Charles Masson"))
+                .unwrap()
+                .spies
+                .count(Color::Red),
+            1
+        );
+    }
+
+    #[test]
+    fn test_spy_movement_abilities() {
+        // Strange Bedfellows makes Murad Beg and Charles Masson neighbors
+        let game = spy_game("Jan Prosper Witkiewicz");
+        let witkiewicz = |steps| move_with("Jan Prosper Witkiewicz", steps);
+
+        assert_eq!(
+            game.validate(&witkiewicz(vec![spy("Murad Beg", "Charles Masson")])),
+            Ok(())
+        );
+        assert!(game
+            .validate(&witkiewicz(vec![spy("Murad Beg", "Claude Wade")]))
+            .is_err());
+
+        // Well Connected lets a spy skip over a card
+        let game = spy_game("Arthur Conolly");
+        let conolly = |steps| move_with("Arthur Conolly", steps);
+
+        assert_eq!(
+            game.validate(&conolly(vec![spy("Murad Beg", "Claude Wade")])),
+            Ok(())
+        );
+        assert_eq!(
+            game.validate(&conolly(vec![spy("Murad Beg", "Charles Masson")])),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_legal_moves() {
+        let mut game = new_game();
+        red_mut(&mut game)
+            .court
+            .cards
+            .push_back(in_court("Hari Singh Nalwa"));
+        add_armies(&mut game, Punjab, 1);
+        add_road(&mut game, Kabul, Punjab);
+
+        let actions = game.legal_actions();
+        assert!(!actions.contains(&move_with("Hari Singh Nalwa", vec![])));
+        assert_eq!(
+            game.validate(&move_with("Hari Singh Nalwa", vec![])),
+            Err(ActionError::NothingToDo(CardAction::Move))
+        );
+        assert!(actions.contains(&move_with(
+            "Hari Singh Nalwa",
+            vec![army(Punjab, Kabul), army(Kabul, Punjab)]
+        )));
+        assert!(!actions.contains(&move_with("Hari Singh Nalwa", vec![army(Punjab, Kandahar)])));
+    }
+}
@@ -0,0 +1,403 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cards::court::{self, SpecialAbility},
+    market::Column,
+    player::{Color, CourtCard, PlayerState},
+    primitives::Suit,
+    util::unique_max_by_key,
+};
+
+use super::Game;
+
+/// The most gifts a player can buy
+pub const MAX_GIFTS: i8 = 3;
+
+/// The cost of the betray action
+pub const BETRAY_COST: i8 = 2;
+
+/// The cost of each block bought with the build action
+pub const BUILD_COST: i8 = 2;
+
+/// The most blocks that can be bought with a single build action
+pub const MAX_BUILDS: usize = 3;
+
+/// A bribe that has to be paid to another player
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Bribe {
+    /// The player being bribed
+    pub to: Color,
+
+    /// The number of rupees they're owed
+    pub amount: i8,
+}
+
+/// Works out what things cost, taking the state of the game (the climate and
+/// any event effects) into account. Everything the engine charges for is
+/// priced here.
+#[derive(Debug, Clone, Copy)]
+pub struct Pricing<'a> {
+    game: &'a Game,
+}
+
+impl Game {
+    pub fn pricing(&self) -> Pricing<'_> {
+        Pricing { game: self }
+    }
+}
+
+impl Pricing<'_> {
+    /// The number of rupees placed on each card to the left of a purchased
+    /// card. Purchases cost double while military is favored.
+    pub fn purchase_rate(&self) -> i8 {
+        match self.game.climate {
+            Suit::Military => 2,
+            _ => 1,
+        }
+    }
+
+    /// The cost to purchase the card in a market column
+    pub fn purchase_cost(&self, column: Column) -> i8 {
+        column.cost() as i8 * self.purchase_rate()
+    }
+
+    /// The cost of a player's next gift, or `None` if they've bought all the
+    /// gifts they can
+    pub fn gift_cost(&self, player: &PlayerState) -> Option<i8> {
+        let gifts = player.gifts.count();
+        (gifts < MAX_GIFTS).then_some(2 * (gifts + 1))
+    }
+
+    /// The cost of the betray action
+    pub fn betray_cost(&self) -> i8 {
+        BETRAY_COST
+    }
+
+    /// The number of blocks placed for each one bought with the build
+    /// action. Nation Building doubles them.
+    fn blocks_per_build(&self, player: &PlayerState) -> usize {
+        match player.effects.nation_building {
+            true => 2,
+            false => 1,
+        }
+    }
+
+    /// Infrastructure places an extra block for free
+    fn free_builds(&self, player: &PlayerState) -> usize {
+        player
+            .court
+            .cards
+            .iter()
+            .any(|card| card.ability == Some(SpecialAbility::Infrastructure)) as usize
+    }
+
+    /// The most blocks a player can place with a single build action
+    pub fn max_builds(&self, player: &PlayerState) -> usize {
+        MAX_BUILDS * self.blocks_per_build(player) + self.free_builds(player)
+    }
+
+    /// The cost of placing some blocks with a single build action. At least
+    /// one block has to be bought to get a free one from Infrastructure.
+    pub fn build_cost(&self, player: &PlayerState, blocks: usize) -> i8 {
+        let bought = blocks
+            .saturating_sub(self.free_builds(player))
+            .div_ceil(self.blocks_per_build(player))
+            .max(1);
+
+        bought as i8 * BUILD_COST
+    }
+
+    /// The rupees another player could take from a player in taxes. A rupee
+    /// is sheltered for every economic star in their court.
+    pub fn taxable_rupees(&self, player: &PlayerState) -> i8 {
+        (player.rupees.count() - player.star_count(Suit::Economic)).max(0)
+    }
+
+    /// The bribe a player has to pay to use one of their court cards. A card
+    /// is held hostage by the player with strictly the most spies on it; if
+    /// that's someone else, they're owed a rupee per spy.
+    pub fn hostage_bribe(&self, player: Color, card: &CourtCard) -> Option<Bribe> {
+        let state = &self.game.players[player].state;
+
+        if self.ignores_bribes(state)
+            || state
+                .court
+                .cards
+                .iter()
+                .any(|card| card.ability == Some(SpecialAbility::CivilServiceReforms))
+        {
+            return None;
+        }
+
+        let spies = self
+            .game
+            .players
+            .iter()
+            .map(|other| (other.color, card.spies.count(other.color)))
+            .filter(|&(_, count)| count > 0);
+
+        unique_max_by_key(spies, |&(_, count)| count)
+            .filter(|&(holder, _)| holder != player)
+            .map(|(to, amount)| Bribe { to, amount })
+    }
+
+    /// The bribe a player has to pay to play a card into a region ruled by
+    /// another player: a rupee for each of the ruler's tribes there
+    pub fn play_bribe(&self, player: Color, card: &court::Card) -> Option<Bribe> {
+        let state = &self.game.players[player].state;
+
+        if self.ignores_bribes(state)
+            || state
+                .court
+                .cards
+                .iter()
+                .any(|card| card.ability == Some(SpecialAbility::CharismaticCourtiers))
+        {
+            return None;
+        }
+
+        let map = &self.game.map;
+        map.ruler(card.region, &self.game.players)
+            .filter(|&ruler| ruler != player)
+            .map(|ruler| Bribe {
+                to: ruler,
+                amount: map.tribes(card.region).count(ruler),
+            })
+    }
+
+    /// Disregard for Customs cancels all bribes, and Courtly Manners lets its
+    /// owner ignore them
+    fn ignores_bribes(&self, player: &PlayerState) -> bool {
+        self.game.effects.disregard_for_customs || player.effects.courtly_manners
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        cards::CardId,
+        game::{
+            test_util::{court_card, game_with, id, in_court},
+            Action, GameEvent,
+        },
+        map::Region,
+        market::Row,
+        player::Side,
+        primitives::Coalition,
+        rupees::RupeeSet,
+    };
+
+    fn new_game() -> Game {
+        game_with(
+            &[Color::Red, Color::Blue, Color::Yellow],
+            Coalition::Afghanistan,
+            3,
+        )
+    }
+
+    /// Put some spies on a card
+    fn add_spies(card: &mut CourtCard, game: &mut Game, player: Color, count: i8) {
+        let spies = game.players[player].state.bank.take_up_to(count);
+        card.spies.add(player, spies);
+    }
+
+    #[test]
+    fn test_military_doubles_purchases() {
+        let mut game = new_game();
+
+        game.climate = Suit::Political;
+        assert_eq!(game.pricing().purchase_cost(Column::Three), 3);
+
+        game.try_set_climate(Suit::Military);
+        assert_eq!(game.pricing().purchase_rate(), 2);
+        assert_eq!(game.pricing().purchase_cost(Column::Zero), 0);
+        assert_eq!(game.pricing().purchase_cost(Column::Three), 6);
+
+        // Only the first two columns are affordable with 4 rupees
+        let purchases = game
+            .legal_actions()
+            .into_iter()
+            .filter(|action| matches!(action, Action::Purchase { .. }))
+            .count();
+        assert_eq!(purchases, 6);
+
+        let events = game
+            .apply(Action::Purchase {
+                row: Row::Top,
+                column: Column::Two,
+                choice: None,
+            })
+            .unwrap();
+
+        assert!(matches!(
+            &events[0],
+            GameEvent::CardPurchased { receipt, .. } if receipt.cost == 4
+        ));
+        assert_eq!(game.players[Color::Red].state.rupees.count(), 0);
+    }
+
+    #[test]
+    fn test_gift_cost() {
+        let mut game = new_game();
+        let pricing = game.pricing();
+        let player = &game.players[Color::Red].state;
+        assert_eq!(pricing.gift_cost(player), Some(2));
+
+        let state = &mut game.players[Color::Red].state;
+        let gifts = state.bank.take_up_to(2);
+        state.gifts.add(gifts);
+        assert_eq!(
+            game.pricing().gift_cost(&game.players[Color::Red].state),
+            Some(6)
+        );
+
+        let state = &mut game.players[Color::Red].state;
+        let gift = state.bank.take_up_to(1);
+        state.gifts.add(gift);
+        assert_eq!(
+            game.pricing().gift_cost(&game.players[Color::Red].state),
+            None
+        );
+    }
+
+    #[test]
+    fn test_hostage_bribe() {
+        let mut game = new_game();
+        let mut card = in_court("Kabul Bazaar");
+
+        // No spies, no bribe
+        assert_eq!(game.pricing().hostage_bribe(Color::Red, &card), None);
+
+        // Blue has the most spies
+        add_spies(&mut card, &mut game, Color::Blue, 2);
+        add_spies(&mut card, &mut game, Color::Red, 1);
+        assert_eq!(
+            game.pricing().hostage_bribe(Color::Red, &card),
+            Some(Bribe {
+                to: Color::Blue,
+                amount: 2
+            })
+        );
+
+        // A tie for the most spies means nobody holds the card
+        add_spies(&mut card, &mut game, Color::Yellow, 2);
+        assert_eq!(game.pricing().hostage_bribe(Color::Red, &card), None);
+
+        // Nobody bribes themselves
+        add_spies(&mut card, &mut game, Color::Red, 2);
+        assert_eq!(game.pricing().hostage_bribe(Color::Red, &card), None);
+    }
+
+    #[test]
+    fn test_bribe_exemptions() {
+        let mut game = new_game();
+        let mut card = in_court("Kabul Bazaar");
+        add_spies(&mut card, &mut game, Color::Blue, 1);
+
+        game.effects.disregard_for_customs = true;
+        assert_eq!(game.pricing().hostage_bribe(Color::Red, &card), None);
+        game.effects.disregard_for_customs = false;
+
+        game.players[Color::Red].state.effects.courtly_manners = true;
+        assert_eq!(game.pricing().hostage_bribe(Color::Red, &card), None);
+        game.players[Color::Red].state.effects.courtly_manners = false;
+
+        game.players[Color::Red]
+            .state
+            .court
+            .cards
+            .push_back(in_court("Yar Mohammad Alikozai"));
+        assert_eq!(game.pricing().hostage_bribe(Color::Red, &card), None);
+    }
+
+    #[test]
+    fn test_card_actions_pay_bribes() {
+        let mut game = new_game();
+        game.climate = Suit::Economic;
+
+        let mut card = in_court("Kabul Bazaar");
+        add_spies(&mut card, &mut game, Color::Blue, 2);
+        game.players[Color::Red].state.court.cards.push_back(card);
+
+        let bazaar = CardId::from_name("Kabul Bazaar").unwrap();
+
+        // Three rupees covers the bribe, but not the gift as well
+        game.players[Color::Red].state.rupees.take_up_to(1);
+        assert!(game.apply(Action::Gift { card: bazaar }).is_err());
+
+        game.players[Color::Red].state.rupees.add(RupeeSet::new(1));
+        let events = game.apply(Action::Gift { card: bazaar }).unwrap();
+
+        assert_eq!(
+            events[0],
+            GameEvent::BribePaid {
+                player: Color::Red,
+                to: Color::Blue,
+                amount: 2
+            }
+        );
+        assert_eq!(game.players[Color::Red].state.rupees.count(), 0);
+        assert_eq!(game.players[Color::Blue].state.rupees.count(), 6);
+    }
+
+    #[test]
+    fn test_playing_into_a_ruled_region_pays_bribes() {
+        let mut game = new_game();
+        let play = Action::Play {
+            card: id("Jan-Fishan Khan"),
+            side: Side::Left,
+            roads: vec![],
+            spies: vec![],
+        };
+
+        game.place_tribe(Color::Blue, Region::Kabul).unwrap();
+        game.place_tribe(Color::Blue, Region::Kabul).unwrap();
+
+        let card = court_card("Jan-Fishan Khan");
+        assert_eq!(
+            game.pricing().play_bribe(Color::Red, &card),
+            Some(Bribe {
+                to: Color::Blue,
+                amount: 2
+            })
+        );
+        assert_eq!(game.pricing().play_bribe(Color::Blue, &card), None);
+
+        // Charismatic Courtiers skips the bribe
+        let court = &mut game.players[Color::Red].state.court.cards;
+        court.push_back(in_court("Mohan Lal"));
+        assert_eq!(game.pricing().play_bribe(Color::Red, &card), None);
+        game.players[Color::Red].state.court.cards.clear();
+
+        game.effects.disregard_for_customs = true;
+        assert_eq!(game.pricing().play_bribe(Color::Red, &card), None);
+        game.effects.disregard_for_customs = false;
+
+        // A player who can't pay the ruler can't play there
+        let state = &mut game.players[Color::Red].state;
+        state.hand.cards.push(card);
+        let rupees = state.rupees.take_up_to(3);
+        assert!(matches!(
+            game.apply(play.clone()),
+            Err(crate::game::ActionError::CannotAfford {
+                cost: 2,
+                available: 1
+            })
+        ));
+
+        game.players[Color::Red].state.rupees.add(rupees);
+        let events = game.apply(play).unwrap();
+        assert_eq!(
+            events[0],
+            GameEvent::BribePaid {
+                player: Color::Red,
+                to: Color::Blue,
+                amount: 2
+            }
+        );
+        assert_eq!(game.players[Color::Red].state.rupees.count(), 2);
+        assert_eq!(game.players[Color::Blue].state.rupees.count(), 6);
+    }
+}
@@ -0,0 +1,505 @@
+use std::iter;
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
+use thiserror::Error;
+
+use crate::{cards, map::Region, player::Color, primitives::Coalition};
+
+use super::{BuildTarget, Decision, Game, GameEvent, GameOver, Request};
+
+/// A player's answer to a [`Request`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Response {
+    /// Discard the card at this index in the player's hand
+    Discard(usize),
+
+    /// Discard the card at this index in the player's court
+    DiscardCourt(usize),
+
+    /// Take a block from each of these places on the map
+    Blocks(Vec<BuildTarget>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ResponseError {
+    #[error("the game is over, and {:?} won", .0.winner)]
+    GameOver(GameOver),
+
+    #[error("{0:?} has nothing to decide")]
+    NoRequest(Color),
+
+    #[error("{response:?} doesn't answer {decision:?}")]
+    WrongResponse {
+        decision: Decision,
+        response: Response,
+    },
+
+    #[error("there's no card at index {0} in your hand")]
+    InvalidIndex(usize),
+
+    #[error("there's no card at index {0} in your court")]
+    InvalidCourtIndex(usize),
+
+    #[error("expected {expected} blocks, got {found}")]
+    WrongCount { expected: i8, found: usize },
+
+    #[error("not enough {coalition:?} blocks at {site:?}")]
+    NoBlock {
+        site: BuildTarget,
+        coalition: Coalition,
+    },
+}
+
+impl Game {
+    /// Check that the game isn't waiting on anyone to make a decision, which
+    /// blocks all normal actions
+    pub(super) fn check_no_requests(&self) -> Result<(), Request> {
+        match self.requests.front() {
+            Some(&request) => Err(request),
+            None => Ok(()),
+        }
+    }
+
+    /// Check a player's response to the next request they have to answer,
+    /// without applying it. Returns the request it answers.
+    pub fn check_response(
+        &self,
+        player: Color,
+        response: &Response,
+    ) -> Result<Request, ResponseError> {
+        self.check_not_over().map_err(ResponseError::GameOver)?;
+
+        let request = *self
+            .requests
+            .next_for(player)
+            .ok_or(ResponseError::NoRequest(player))?;
+
+        let state = &self.players[player].state;
+
+        match (request.decision, response) {
+            (Decision::ConfidenceFailure | Decision::LeverageDebt, &Response::Discard(index)) => {
+                match index < state.hand.cards.len() {
+                    true => Ok(request),
+                    false => Err(ResponseError::InvalidIndex(index)),
+                }
+            }
+            (Decision::LeverageDebt, &Response::DiscardCourt(index)) => {
+                match index < state.court.cards.len() {
+                    true => Ok(request),
+                    false => Err(ResponseError::InvalidCourtIndex(index)),
+                }
+            }
+            (
+                Decision::InsurrectionArmies {
+                    region,
+                    coalition,
+                    count,
+                },
+                Response::Blocks(sites),
+            ) => {
+                if sites.len() != count.max(0) as usize {
+                    return Err(ResponseError::WrongCount {
+                        expected: count,
+                        found: sites.len(),
+                    });
+                }
+
+                let taken = sites.iter().copied().counts();
+
+                for &site in sites {
+                    if taken[&site] as i8 > self.blocks_at(site, coalition, region) {
+                        return Err(ResponseError::NoBlock { site, coalition });
+                    }
+                }
+
+                Ok(request)
+            }
+            (decision, response) => Err(ResponseError::WrongResponse {
+                decision,
+                response: response.clone(),
+            }),
+        }
+    }
+
+    /// Answer the next request a player has to make. Players may answer
+    /// their requests in any order relative to each other, but each player's
+    /// own requests are answered in the order they were made.
+    pub fn respond(
+        &mut self,
+        player: Color,
+        response: Response,
+    ) -> Result<Vec<GameEvent>, ResponseError> {
+        let request = self.check_response(player, &response)?;
+        self.requests.take_next_for(player);
+
+        let mut events = Vec::new();
+
+        match (request.decision, response) {
+            (_, Response::Discard(index)) => {
+                let card = self.players[player].state.hand.cards.remove(index);
+
+                events.push(GameEvent::CardDiscarded {
+                    player,
+                    card: card.id(),
+                });
+                self.discard.push(cards::Card::Court(card));
+            }
+            (_, Response::DiscardCourt(index)) => {
+                let card = self.players[player].state.court.cards[index].id();
+                self.discard_court_card(player, card, &mut events);
+            }
+            (
+                Decision::InsurrectionArmies {
+                    region, coalition, ..
+                },
+                Response::Blocks(sites),
+            ) => {
+                for &site in &sites {
+                    let block = match site {
+                        BuildTarget::Army(from) => {
+                            events.push(GameEvent::ArmiesRemoved {
+                                region: from,
+                                coalition,
+                                count: 1,
+                            });
+                            self.map.take_armies(from, 1, coalition)
+                        }
+                        BuildTarget::Road(border) => {
+                            events.push(GameEvent::RoadRemoved { border, coalition });
+                            self.map
+                                .remove_roads(border, 1, coalition)
+                                .expect("response was checked")
+                        }
+                    };
+
+                    self.map.add_armies(region, block);
+                }
+
+                events.push(GameEvent::ArmiesPlaced {
+                    region,
+                    coalition,
+                    count: sites.len() as i8,
+                });
+            }
+            _ => unreachable!("response was checked"),
+        }
+
+        self.resume_end_turn(&mut events);
+        Ok(events)
+    }
+
+    /// List every valid response to the next request a player has to answer
+    pub fn legal_responses(&self, player: Color) -> Vec<Response> {
+        let request = match self.requests.next_for(player) {
+            Some(request) => request,
+            None => return Vec::new(),
+        };
+
+        let state = &self.players[player].state;
+        let hand = (0..state.hand.cards.len()).map(Response::Discard);
+        let court = (0..state.court.cards.len()).map(Response::DiscardCourt);
+
+        let responses = match request.decision {
+            Decision::ConfidenceFailure => hand.collect_vec(),
+            Decision::LeverageDebt => hand.chain(court).collect_vec(),
+            Decision::InsurrectionArmies {
+                region,
+                coalition,
+                count,
+            } => {
+                let armies = Region::iter().map(BuildTarget::Army);
+                let roads = self
+                    .map
+                    .borders()
+                    .map(|(border, _)| BuildTarget::Road(border));
+
+                // One entry for every block that could be taken
+                let blocks = armies
+                    .chain(roads)
+                    .flat_map(|site| {
+                        let available = self.blocks_at(site, coalition, region);
+                        iter::repeat_n(site, available.max(0) as usize)
+                    })
+                    .collect_vec();
+
+                blocks
+                    .into_iter()
+                    .combinations(count.max(0) as usize)
+                    .unique()
+                    .map(Response::Blocks)
+                    .collect()
+            }
+        };
+
+        responses
+            .into_iter()
+            .filter(|response| self.check_response(player, response).is_ok())
+            .collect()
+    }
+
+    /// The number of blocks of a coalition at a site that could be moved
+    /// into `region`
+    fn blocks_at(&self, site: BuildTarget, coalition: Coalition, region: Region) -> i8 {
+        match site {
+            BuildTarget::Army(from) if from == region => 0,
+            BuildTarget::Army(from) => self.map.armies(from).count(coalition),
+            BuildTarget::Road(border) => self
+                .map
+                .roads_on(border)
+                .map_or(0, |roads| roads.count(coalition)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        game::{
+            test_util::{court_card, game_with, id, in_court},
+            Action, ActionError,
+        },
+        map::Border,
+        market::{Column, Row},
+    };
+
+    fn new_game() -> Game {
+        game_with(&[Color::Red, Color::Blue], Coalition::Afghanistan, 3)
+    }
+
+    #[test]
+    fn test_requests_block_actions() {
+        let mut game = new_game();
+        let request = Request {
+            player: Color::Blue,
+            decision: Decision::ConfidenceFailure,
+        };
+
+        game.players[Color::Blue]
+            .state
+            .hand
+            .cards
+            .push(court_card("Kabul Bazaar"));
+        game.requests.push(request);
+
+        let purchase = Action::Purchase {
+            row: Row::Top,
+            column: Column::Zero,
+            choice: None,
+        };
+
+        assert_eq!(
+            game.apply(purchase.clone()),
+            Err(ActionError::WaitingOn(request))
+        );
+        assert!(game.legal_actions().is_empty());
+
+        // Only blue has anything to answer
+        assert_eq!(
+            game.respond(Color::Red, Response::Discard(0)),
+            Err(ResponseError::NoRequest(Color::Red))
+        );
+        assert_eq!(
+            game.respond(Color::Blue, Response::Discard(1)),
+            Err(ResponseError::InvalidIndex(1))
+        );
+        assert_eq!(
+            game.respond(Color::Blue, Response::Blocks(Vec::new())),
+            Err(ResponseError::WrongResponse {
+                decision: Decision::ConfidenceFailure,
+                response: Response::Blocks(Vec::new()),
+            })
+        );
+
+        assert_eq!(game.legal_responses(Color::Blue), [Response::Discard(0)]);
+        game.respond(Color::Blue, Response::Discard(0)).unwrap();
+
+        assert!(game.requests.is_empty());
+        assert!(game.validate(&purchase).is_ok());
+    }
+
+    #[test]
+    fn test_insurrection_with_empty_supply() {
+        let mut game = new_game();
+        game.players[Color::Red]
+            .state
+            .court
+            .cards
+            .push_back(in_court("Prince Akbar Khan"));
+
+        // Every Afghan block but one is on the map already, and the British
+        // have enough to stop the Afghans from being dominant
+        let armies = game.blocks.take_up_to(10, Coalition::Afghanistan);
+        game.map.add_armies(Region::Herat, armies);
+        let roads = game.blocks.take_up_to(1, Coalition::Afghanistan);
+        game.map
+            .add_roads(Border::new(Region::Kabul, Region::Punjab), roads)
+            .unwrap();
+        let armies = game.blocks.take_up_to(10, Coalition::Britain);
+        game.map.add_armies(Region::Persia, armies);
+
+        game.resolve_dominance_check();
+
+        // The supply had one block left for the insurrection in Kabul, so one
+        // more has to be moved
+        assert_eq!(
+            game.requests.iter().collect_vec(),
+            [&Request {
+                player: Color::Red,
+                decision: Decision::InsurrectionArmies {
+                    region: Region::Kabul,
+                    coalition: Coalition::Afghanistan,
+                    count: 1,
+                },
+            }]
+        );
+
+        let from_herat = Response::Blocks(vec![BuildTarget::Army(Region::Herat)]);
+        let from_road = Response::Blocks(vec![BuildTarget::Road(Border::new(
+            Region::Kabul,
+            Region::Punjab,
+        ))]);
+
+        assert_eq!(
+            game.legal_responses(Color::Red),
+            [from_herat.clone(), from_road]
+        );
+        assert_eq!(
+            game.check_response(
+                Color::Red,
+                &Response::Blocks(vec![BuildTarget::Army(Region::Kabul)])
+            ),
+            Err(ResponseError::NoBlock {
+                site: BuildTarget::Army(Region::Kabul),
+                coalition: Coalition::Afghanistan,
+            })
+        );
+
+        let events = game.respond(Color::Red, from_herat).unwrap();
+        assert_eq!(
+            events,
+            [
+                GameEvent::ArmiesRemoved {
+                    region: Region::Herat,
+                    coalition: Coalition::Afghanistan,
+                    count: 1,
+                },
+                GameEvent::ArmiesPlaced {
+                    region: Region::Kabul,
+                    coalition: Coalition::Afghanistan,
+                    count: 1,
+                },
+            ]
+        );
+
+        assert_eq!(
+            game.map.armies(Region::Kabul).count(Coalition::Afghanistan),
+            2
+        );
+        assert_eq!(
+            game.map.armies(Region::Herat).count(Coalition::Afghanistan),
+            9
+        );
+        assert!(game.requests.is_empty());
+    }
+
+    #[test]
+    fn test_leverage_debt() {
+        let mut game = new_game();
+
+        // Money Lenders has leverage, and red can only pay one of the two
+        // rupees back when it goes
+        let player = &mut game.players[Color::Red].state;
+        player
+            .court
+            .cards
+            .extend(["Money Lenders", "Kabul Bazaar", "Mir Masjidi"].map(in_court));
+        player.hand.cards.push(court_card("Bala Hissar"));
+        player.rupees.take_up_to(player.rupees.count() - 1);
+
+        let mut events = Vec::new();
+        game.discard_court_card(Color::Red, id("Money Lenders"), &mut events);
+
+        assert_eq!(
+            events,
+            [
+                GameEvent::CardDiscarded {
+                    player: Color::Red,
+                    card: id("Money Lenders"),
+                },
+                GameEvent::LeverageRepaid {
+                    player: Color::Red,
+                    card: id("Money Lenders"),
+                    amount: 1,
+                },
+            ]
+        );
+        assert_eq!(game.players[Color::Red].state.rupees.count(), 0);
+        assert_eq!(
+            game.requests.iter().copied().collect::<Vec<_>>(),
+            [Request {
+                player: Color::Red,
+                decision: Decision::LeverageDebt,
+            }]
+        );
+
+        // The debt can be paid from the hand or the court
+        assert_eq!(
+            game.legal_responses(Color::Red),
+            [
+                Response::Discard(0),
+                Response::DiscardCourt(0),
+                Response::DiscardCourt(1),
+            ]
+        );
+        assert_eq!(
+            game.respond(Color::Red, Response::DiscardCourt(2)),
+            Err(ResponseError::InvalidCourtIndex(2))
+        );
+
+        game.respond(Color::Red, Response::DiscardCourt(1)).unwrap();
+        assert!(game.requests.is_empty());
+        assert_eq!(game.players[Color::Red].state.court.cards.len(), 1);
+    }
+
+    #[test]
+    fn test_losing_political_cards_removes_tribes() {
+        let mut game = new_game();
+
+        // Murad Beg is red's only political card in Kabul
+        game.players[Color::Red]
+            .state
+            .court
+            .cards
+            .extend(["Murad Beg", "Mir Masjidi"].map(in_court));
+        game.place_tribe(Color::Red, Region::Kabul).unwrap();
+        game.place_tribe(Color::Red, Region::Kabul).unwrap();
+
+        let mut events = Vec::new();
+        game.discard_court_card(Color::Red, id("Mir Masjidi"), &mut events);
+        assert_eq!(game.map.tribes(Region::Kabul).count(Color::Red), 2);
+
+        events.clear();
+        game.discard_court_card(Color::Red, id("Murad Beg"), &mut events);
+
+        assert_eq!(
+            events,
+            [
+                GameEvent::CardDiscarded {
+                    player: Color::Red,
+                    card: id("Murad Beg"),
+                },
+                GameEvent::TribesRemoved {
+                    player: Color::Red,
+                    region: Region::Kabul,
+                    count: 2,
+                },
+            ]
+        );
+        assert_eq!(game.map.tribes(Region::Kabul).count(Color::Red), 0);
+        assert_eq!(game.players[Color::Red].state.bank.count(), 10);
+    }
+}
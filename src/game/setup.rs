@@ -0,0 +1,169 @@
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use thiserror::Error;
+
+use crate::{
+    blocks::BlockSet,
+    deck::Deck,
+    map::Map,
+    market::Market,
+    player::{DuplicateColor, PlayerInit, PlayerSet},
+    primitives::Suit,
+};
+
+use super::{Effects, Game, RequestQueue, TurnState};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Error)]
+pub enum SetupError {
+    #[error("a game needs between 2 and 5 players, not {0}")]
+    PlayerCount(usize),
+
+    #[error(transparent)]
+    DuplicateColor(#[from] DuplicateColor),
+}
+
+impl Game {
+    /// Set up a new game, following the rulebook setup. The players are given
+    /// in turn order, each with their chosen loyalty, and start with 4 rupees.
+    /// The deck is built for the number of players, the market is dealt, and
+    /// a random player is chosen to go first.
+    ///
+    /// All randomness comes from `seed`, so the same players and seed always
+    /// produce the same game.
+    pub fn new(
+        players: impl IntoIterator<Item = PlayerInit>,
+        seed: u64,
+    ) -> Result<Self, SetupError> {
+        let players = PlayerSet::new(players)?;
+        let player_count = players.players().len();
+
+        if !(2..=5).contains(&player_count) {
+            return Err(SetupError::PlayerCount(player_count));
+        }
+
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+        let mut deck = Deck::new(&mut rng, player_count);
+        let mut market = Market::new();
+        market.fill_from(&mut deck.draws());
+
+        Ok(Game {
+            map: Map::new(),
+            market,
+            deck,
+            climate: Suit::Political,
+            effects: Effects::new(),
+            blocks: BlockSet::new_tray(),
+            players,
+            discard: Vec::new(),
+            turn: TurnState {
+                player: rng.gen_range(0..player_count),
+                actions_taken: 0,
+                ending: false,
+            },
+            requests: RequestQueue::new(),
+            game_over: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        market::{Column, Row},
+        player::Color,
+        primitives::Coalition,
+    };
+
+    fn player(color: Color, loyalty: Coalition) -> PlayerInit {
+        PlayerInit {
+            color,
+            loyalty,
+            name: format!("{:?}", color),
+        }
+    }
+
+    fn three_players() -> [PlayerInit; 3] {
+        [
+            player(Color::Red, Coalition::Britain),
+            player(Color::Blue, Coalition::Russia),
+            player(Color::Yellow, Coalition::Afghanistan),
+        ]
+    }
+
+    fn market_ids(game: &Game) -> Vec<u8> {
+        [Row::Top, Row::Bottom]
+            .into_iter()
+            .flat_map(|row| game.market.get_row(row).view())
+            .map(|card| card.as_ref().unwrap().id().number())
+            .collect()
+    }
+
+    #[test]
+    fn test_setup() {
+        let game = Game::new(three_players(), 7).unwrap();
+
+        assert_eq!(game.deck.len(), 6 * 8 + 10 - 12);
+        assert_eq!(game.market.get_row(Row::Top).count(), 6);
+        assert_eq!(game.market.get_row(Row::Bottom).count(), 6);
+        assert!(game
+            .market
+            .get_row(Row::Top)
+            .get_card(Column::Zero)
+            .is_some());
+
+        assert_eq!(game.climate, Suit::Political);
+        assert!(game.turn.player < 3);
+        assert_eq!(game.turn.actions_taken, 0);
+
+        game.players.iter().for_each(|player| {
+            assert_eq!(player.state.rupees.count(), 4);
+            assert_eq!(player.state.bank.count(), 10);
+        });
+
+        assert_eq!(game.players[Color::Blue].state.loyalty, Coalition::Russia);
+    }
+
+    #[test]
+    fn test_setup_is_deterministic() {
+        let first = Game::new(three_players(), 99).unwrap();
+        let second = Game::new(three_players(), 99).unwrap();
+
+        assert_eq!(market_ids(&first), market_ids(&second));
+        assert_eq!(first.turn.player, second.turn.player);
+    }
+
+    #[test]
+    fn test_first_player_varies() {
+        let first_players: Vec<usize> = (0..20)
+            .map(|seed| Game::new(three_players(), seed).unwrap().turn.player)
+            .collect();
+
+        assert!((0..3).all(|player| first_players.contains(&player)));
+    }
+
+    #[test]
+    fn test_player_count() {
+        assert_eq!(
+            Game::new([player(Color::Red, Coalition::Britain)], 0).unwrap_err(),
+            SetupError::PlayerCount(1)
+        );
+    }
+
+    #[test]
+    fn test_duplicate_color() {
+        assert_eq!(
+            Game::new(
+                [
+                    player(Color::Red, Coalition::Britain),
+                    player(Color::Red, Coalition::Russia),
+                ],
+                0
+            )
+            .unwrap_err(),
+            SetupError::DuplicateColor(DuplicateColor(Color::Red))
+        );
+    }
+}
@@ -0,0 +1,49 @@
+//! Setup shared by the game module's tests
+
+use crate::{
+    cards::{court, CardId},
+    player::{Color, CourtCard, PlayerInit, PlayerState},
+    primitives::Coalition,
+};
+
+use super::Game;
+
+pub fn id(name: &str) -> CardId {
+    CardId::from_name(name).unwrap()
+}
+
+pub fn court_card(name: &str) -> court::Card {
+    court::Card::from_id(id(name)).unwrap()
+}
+
+/// A court card as it would be after being played
+pub fn in_court(name: &str) -> CourtCard {
+    CourtCard::new(court_card(name))
+}
+
+/// A game between the given players, all loyal to the same coalition, where
+/// it's the first player's turn
+pub fn game_with(colors: &[Color], loyalty: Coalition, seed: u64) -> Game {
+    let players = colors.iter().map(|&color| PlayerInit {
+        color,
+        loyalty,
+        name: format!("{:?}", color),
+    });
+
+    let mut game = Game::new(players, seed).unwrap();
+    game.turn.player = 0;
+    game
+}
+
+/// A two player British game where it's red's turn
+pub fn new_game() -> Game {
+    game_with(&[Color::Red, Color::Blue], Coalition::Britain, 4)
+}
+
+pub fn red(game: &Game) -> &PlayerState {
+    &game.players[Color::Red].state
+}
+
+pub fn red_mut(game: &mut Game) -> &mut PlayerState {
+    &mut game.players[Color::Red].state
+}
@@ -0,0 +1,85 @@
+use thiserror::Error;
+
+use crate::{map::Region, player::Color};
+
+use super::Game;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Error)]
+pub enum TribeError {
+    #[error("{0:?} has no cylinders left to place")]
+    NoCylinders(Color),
+
+    #[error("{player:?} has no tribes in {region:?}")]
+    NoTribe { player: Color, region: Region },
+}
+
+impl Game {
+    /// Move a cylinder from a player's bank onto a region as a tribe
+    pub fn place_tribe(&mut self, player: Color, region: Region) -> Result<(), TribeError> {
+        let tribe = self.players[player].state.bank.take_up_to(1);
+
+        if tribe.count() == 0 {
+            return Err(TribeError::NoCylinders(player));
+        }
+
+        self.map.add_tribes(region, player, tribe);
+        Ok(())
+    }
+
+    /// Return one of a player's tribes in a region to their bank. Returns
+    /// whether it was their last tribe there, which overthrows them in that
+    /// region.
+    pub fn remove_tribe(&mut self, player: Color, region: Region) -> Result<bool, TribeError> {
+        let tribe = self.map.remove_tribes(region, player, 1);
+
+        if tribe.count() == 0 {
+            return Err(TribeError::NoTribe { player, region });
+        }
+
+        self.players[player].state.discard_cylinders(tribe);
+        Ok(self.map.tribes(region).count(player) == 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::game::test_util::new_game;
+
+    #[test]
+    fn test_place_and_remove_tribes() {
+        let mut game = new_game();
+
+        game.place_tribe(Color::Red, Region::Herat).unwrap();
+        game.place_tribe(Color::Red, Region::Herat).unwrap();
+        assert_eq!(game.map.tribes(Region::Herat).count(Color::Red), 2);
+        assert_eq!(game.players[Color::Red].state.bank.count(), 8);
+
+        assert_eq!(game.remove_tribe(Color::Red, Region::Herat), Ok(false));
+        assert_eq!(game.remove_tribe(Color::Red, Region::Herat), Ok(true));
+        assert_eq!(
+            game.remove_tribe(Color::Red, Region::Herat),
+            Err(TribeError::NoTribe {
+                player: Color::Red,
+                region: Region::Herat,
+            })
+        );
+
+        // Every cylinder made it back to the bank
+        assert_eq!(game.players[Color::Red].state.bank.count(), 10);
+        assert_eq!(game.map.total_tribe_counts()[Color::Red], 0);
+    }
+
+    #[test]
+    fn test_empty_bank() {
+        let mut game = new_game();
+        game.players[Color::Blue].state.bank.take_all();
+
+        assert_eq!(
+            game.place_tribe(Color::Blue, Region::Punjab),
+            Err(TribeError::NoCylinders(Color::Blue))
+        );
+        assert_eq!(game.map.tribes(Region::Punjab).total(), 0);
+    }
+}
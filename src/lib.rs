@@ -8,5 +8,6 @@ pub mod market;
 pub mod player;
 pub mod primitives;
 pub mod rupees;
+pub mod save;
 pub mod score;
 mod util;
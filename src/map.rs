@@ -1,10 +1,21 @@
-use std::{cmp, collections::HashMap, iter};
+use std::{
+    cmp,
+    collections::{HashMap, VecDeque},
+    hash::{Hash, Hasher},
+    iter, mem,
+};
 
 use enum_map::{enum_map, Enum, EnumMap};
-use serde::{Deserialize, Serialize};
-use strum::EnumIter;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use strum::{EnumIter, IntoEnumIterator};
+use thiserror::Error;
 
-use crate::{blocks::BlockSet, cylinders::CylinderSet, player, primitives::Coalition};
+use crate::{
+    blocks::BlockSet,
+    cylinders::{CylinderSet, SingleCylinderSet},
+    player::{self, PlayerSet},
+    primitives::Coalition,
+};
 
 #[derive(
     Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, EnumIter, Enum,
@@ -20,7 +31,7 @@ pub enum Region {
 
 pub use Region::*;
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Border {
     front: Region,
     back: Region,
@@ -40,7 +51,15 @@ impl<'de> Deserialize<'de> for Border {
     where
         D: serde::Deserializer<'de>,
     {
-        Deserialize::deserialize(deserializer).map(|(front, back)| Self::new(front, back))
+        let (front, back): (Region, Region) = Deserialize::deserialize(deserializer)?;
+
+        match front == back {
+            true => Err(de::Error::custom(format_args!(
+                "{:?} can't border itself",
+                front
+            ))),
+            false => Ok(Self::new(front, back)),
+        }
     }
 }
 
@@ -62,25 +81,151 @@ impl Border {
     pub fn borders(&self, region: Region) -> bool {
         self.front == region || self.back == region
     }
+
+    /// The region on the other side of this border from `region`, if the
+    /// border touches it
+    pub fn other(&self, region: Region) -> Option<Region> {
+        match region {
+            _ if region == self.front => Some(self.back),
+            _ if region == self.back => Some(self.front),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Error)]
+pub enum BorderError {
+    #[error("{0:?} can't border itself")]
+    SameRegion(Region),
+
+    #[error("{0:?} and {1:?} don't share a border")]
+    NotAdjacent(Region, Region),
+}
+
+/// Which borders a path across the map may cross
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PathFilter {
+    /// Any border on the map
+    Any,
+
+    /// Only borders with at least one road of this coalition
+    Roads(Coalition),
+}
+
+/// The pieces a player has in a region that count towards ruling it
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RulingPieces {
+    pub player: player::Color,
+
+    /// The player's tribes in the region
+    pub tribes: i8,
+
+    /// The armies of the player's coalition in the region
+    pub armies: i8,
+}
+
+impl RulingPieces {
+    pub fn total(&self) -> i8 {
+        self.tribes + self.armies
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+/// Who rules a region, along with every player's ruling pieces there
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegionRule {
+    pub region: Region,
+
+    /// Each player's ruling pieces, in turn order
+    pub pieces: Vec<RulingPieces>,
+
+    /// The player ruling the region, if anyone
+    pub ruler: Option<player::Color>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
 struct RegionOccupants {
     armies: BlockSet,
     tribes: CylinderSet,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
 struct BorderOccupants {
     roads: BlockSet,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Map {
     regions: EnumMap<Region, RegionOccupants>,
+
+    #[serde(with = "border_list")]
     borders: HashMap<Border, BorderOccupants>,
 }
 
+/// Borders are hashed in order, since the hash map's iteration order isn't
+/// fixed
+impl Hash for Map {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.regions.hash(state);
+        self.borders().for_each(|border| border.hash(state));
+    }
+}
+
+/// Borders are serialized as a sorted list of `(border, occupants)` pairs,
+/// because most formats can't use a tuple as a map key, and so that the
+/// output doesn't depend on the hash map's iteration order. A saved list
+/// must have exactly the borders of [`Map::new`].
+mod border_list {
+    use super::*;
+
+    pub fn serialize<S>(
+        borders: &HashMap<Border, BorderOccupants>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut borders: Vec<(&Border, &BorderOccupants)> = borders.iter().collect();
+        borders.sort_by_key(|&(border, _)| *border);
+        serializer.collect_seq(borders)
+    }
+
+    pub fn deserialize<'de, D>(
+        deserializer: D,
+    ) -> Result<HashMap<Border, BorderOccupants>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let list = Vec::<(Border, BorderOccupants)>::deserialize(deserializer)?;
+        let count = list.len();
+        let borders: HashMap<Border, BorderOccupants> = list.into_iter().collect();
+        let expected = Map::new().borders;
+
+        if let Some(border) = borders
+            .keys()
+            .find(|&border| !expected.contains_key(border))
+        {
+            return Err(de::Error::custom(format_args!(
+                "{:?} and {:?} don't share a border",
+                border.front, border.back
+            )));
+        }
+        if let Some(border) = expected
+            .keys()
+            .find(|&border| !borders.contains_key(border))
+        {
+            return Err(de::Error::custom(format_args!(
+                "missing the border between {:?} and {:?}",
+                border.front, border.back
+            )));
+        }
+        if borders.len() != count {
+            return Err(de::Error::custom("a border appears more than once"));
+        }
+
+        Ok(borders)
+    }
+}
+
 impl Map {
     pub fn new() -> Self {
         Self {
@@ -146,6 +291,214 @@ impl Map {
     pub fn add_armies(&mut self, region: Region, blocks: BlockSet) {
         self.regions[region].armies.add(blocks);
     }
+
+    /// Check if two regions share a border on this map
+    pub fn has_border(&self, border: Border) -> bool {
+        self.borders.contains_key(&border)
+    }
+
+    /// Every border on this map touching a region, in order
+    pub fn borders_of(&self, region: Region) -> Vec<Border> {
+        let mut borders: Vec<Border> = self
+            .borders
+            .keys()
+            .copied()
+            .filter(|border| border.borders(region))
+            .collect();
+        borders.sort_unstable();
+        borders
+    }
+
+    /// Every region sharing a border with a region, in order
+    pub fn neighbors(&self, region: Region) -> Vec<Region> {
+        Region::iter()
+            .filter(|&other| other != region && self.has_border(Border::new(region, other)))
+            .collect()
+    }
+
+    /// The shortest path from one region to another, crossing only borders
+    /// allowed by the filter. The path includes both ends, so a path from a
+    /// region to itself is just that region. Among paths of the same length,
+    /// the one through earlier regions is preferred. Returns `None` if there's
+    /// no such path.
+    pub fn path(&self, from: Region, to: Region, filter: PathFilter) -> Option<Vec<Region>> {
+        let mut previous: EnumMap<Region, Option<Region>> = EnumMap::default();
+        let mut queue = VecDeque::from([from]);
+
+        while let Some(region) = queue.pop_front() {
+            if region == to {
+                let mut path = vec![to];
+                let mut step = to;
+                while step != from {
+                    step = previous[step].expect("every queued region has a previous step");
+                    path.push(step);
+                }
+                path.reverse();
+                return Some(path);
+            }
+
+            for next in self.neighbors(region) {
+                let crossable = match filter {
+                    PathFilter::Any => true,
+                    PathFilter::Roads(coalition) => {
+                        self.borders[&Border::new(region, next)]
+                            .roads
+                            .count(coalition)
+                            > 0
+                    }
+                };
+
+                if crossable && next != from && previous[next].is_none() {
+                    previous[next] = Some(region);
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Work out who rules a region. A player rules a region if they have at
+    /// least one tribe there and strictly more ruling pieces than any other
+    /// player, counting their tribes and the armies of their coalition.
+    pub fn rule(&self, region: Region, players: &PlayerSet) -> RegionRule {
+        let occupants = &self.regions[region];
+
+        let pieces: Vec<RulingPieces> = players
+            .iter()
+            .map(|player| RulingPieces {
+                player: player.color,
+                tribes: occupants.tribes.count(player.color),
+                armies: occupants.armies.count(player.state.loyalty),
+            })
+            .collect();
+
+        let ruler = pieces
+            .iter()
+            .filter(|candidate| candidate.tribes > 0)
+            .find(|candidate| {
+                pieces.iter().all(|other| {
+                    other.player == candidate.player || other.total() < candidate.total()
+                })
+            })
+            .map(|candidate| candidate.player);
+
+        RegionRule {
+            region,
+            pieces,
+            ruler,
+        }
+    }
+
+    /// The player ruling a region, if anyone
+    pub fn ruler(&self, region: Region, players: &PlayerSet) -> Option<player::Color> {
+        self.rule(region, players).ruler
+    }
+
+    /// The border between two regions, if they share one on this map
+    pub fn border(&self, front: Region, back: Region) -> Result<Border, BorderError> {
+        if front == back {
+            return Err(BorderError::SameRegion(front));
+        }
+
+        let border = Border::new(front, back);
+        self.occupants(border).map(|_| border)
+    }
+
+    fn occupants(&self, border: Border) -> Result<&BorderOccupants, BorderError> {
+        self.borders
+            .get(&border)
+            .ok_or(BorderError::NotAdjacent(border.front, border.back))
+    }
+
+    fn occupants_mut(&mut self, border: Border) -> Result<&mut BorderOccupants, BorderError> {
+        self.borders
+            .get_mut(&border)
+            .ok_or(BorderError::NotAdjacent(border.front, border.back))
+    }
+
+    /// Every border on this map along with the roads on it, in order
+    pub fn borders(&self) -> impl Iterator<Item = (Border, BlockSet)> {
+        let mut borders: Vec<(Border, BlockSet)> = self
+            .borders
+            .iter()
+            .map(|(&border, occupants)| (border, occupants.roads))
+            .collect();
+        borders.sort_unstable_by_key(|&(border, _)| border);
+        borders.into_iter()
+    }
+
+    /// Add roads to a border. If the border isn't on this map, the blocks
+    /// are handed back with the error.
+    pub fn add_roads(
+        &mut self,
+        border: Border,
+        blocks: BlockSet,
+    ) -> Result<(), (BorderError, BlockSet)> {
+        match self.occupants_mut(border) {
+            Ok(occupants) => {
+                occupants.roads.add(blocks);
+                Ok(())
+            }
+            Err(err) => Err((err, blocks)),
+        }
+    }
+
+    pub fn add_tribes(&mut self, region: Region, player: player::Color, tribes: SingleCylinderSet) {
+        self.regions[region].tribes.add(player, tribes);
+    }
+
+    /// The tribes in a region
+    pub fn tribes(&self, region: Region) -> CylinderSet {
+        self.regions[region].tribes
+    }
+
+    /// Take up to `count` of a player's tribes out of a region
+    pub fn remove_tribes(
+        &mut self,
+        region: Region,
+        player: player::Color,
+        count: i8,
+    ) -> SingleCylinderSet {
+        self.regions[region].tribes.take_up_to(player, count)
+    }
+
+    /// The armies in a region
+    pub fn armies(&self, region: Region) -> BlockSet {
+        self.regions[region].armies
+    }
+
+    /// The roads on a border
+    pub fn roads_on(&self, border: Border) -> Result<BlockSet, BorderError> {
+        self.occupants(border).map(|occupants| occupants.roads)
+    }
+
+    /// Take up to `count` armies of a coalition out of a region
+    pub fn take_armies(&mut self, region: Region, count: i8, coalition: Coalition) -> BlockSet {
+        self.regions[region].armies.take_up_to(count, coalition)
+    }
+
+    /// Remove up to `count` roads of a coalition from a border and return
+    /// them
+    pub fn remove_roads(
+        &mut self,
+        border: Border,
+        count: i8,
+        coalition: Coalition,
+    ) -> Result<BlockSet, BorderError> {
+        self.occupants_mut(border)
+            .map(|occupants| occupants.roads.take_up_to(count, coalition))
+    }
+
+    /// Remove every army in a region and return them
+    pub fn clear_armies(&mut self, region: Region) -> BlockSet {
+        self.regions[region].armies.take_all()
+    }
+
+    /// Remove every tribe in a region and return them
+    pub fn clear_tribes(&mut self, region: Region) -> CylinderSet {
+        mem::take(&mut self.regions[region].tribes)
+    }
 }
 
 impl Default for Map {
@@ -153,3 +506,202 @@ impl Default for Map {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use itertools::Itertools;
+
+    use crate::player::{Color, PlayerInit};
+
+    fn players() -> PlayerSet {
+        PlayerSet::new(
+            [
+                (Color::Red, Coalition::Russia),
+                (Color::Blue, Coalition::Britain),
+                (Color::Black, Coalition::Russia),
+            ]
+            .map(|(color, loyalty)| PlayerInit {
+                color,
+                loyalty,
+                name: format!("{:?}", color),
+            }),
+        )
+        .unwrap()
+    }
+
+    fn add_tribes(map: &mut Map, region: Region, player: Color, count: i8) {
+        map.add_tribes(
+            region,
+            player,
+            SingleCylinderSet::new_bank().take_up_to(count),
+        );
+    }
+
+    fn add_armies(map: &mut Map, region: Region, coalition: Coalition, count: i8) {
+        map.add_armies(region, BlockSet::new_tray().take_up_to(count, coalition));
+    }
+
+    #[test]
+    fn test_neighbors() {
+        let map = Map::new();
+
+        assert_eq!(map.neighbors(Persia), [Transcaspia, Herat]);
+        assert_eq!(map.neighbors(Kabul), [Transcaspia, Herat, Kandahar, Punjab]);
+        assert_eq!(
+            map.borders_of(Punjab),
+            [Border::new(Kabul, Punjab), Border::new(Kandahar, Punjab)]
+        );
+        assert_eq!(Border::new(Kabul, Punjab).other(Punjab), Some(Kabul));
+        assert_eq!(Border::new(Kabul, Punjab).other(Herat), None);
+    }
+
+    #[test]
+    fn test_path() {
+        let mut map = Map::new();
+
+        assert_eq!(map.path(Herat, Herat, PathFilter::Any), Some(vec![Herat]));
+        // Ties go to the path through the earlier regions
+        assert_eq!(
+            map.path(Persia, Punjab, PathFilter::Any),
+            Some(vec![Persia, Transcaspia, Kabul, Punjab])
+        );
+
+        let mut tray = BlockSet::new_tray();
+        for border in [(Persia, Herat), (Herat, Kandahar), (Kandahar, Punjab)] {
+            let road = tray.take_up_to(1, Coalition::Britain);
+            map.add_roads(Border::new(border.0, border.1), road)
+                .unwrap();
+        }
+
+        // Only the long way round has British roads
+        assert_eq!(
+            map.path(Persia, Punjab, PathFilter::Roads(Coalition::Britain)),
+            Some(vec![Persia, Herat, Kandahar, Punjab])
+        );
+        assert_eq!(
+            map.path(Persia, Punjab, PathFilter::Roads(Coalition::Russia)),
+            None
+        );
+        assert_eq!(
+            map.path(Persia, Kabul, PathFilter::Roads(Coalition::Britain)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_ruler() {
+        let players = players();
+        let mut map = Map::new();
+
+        // Nobody rules an empty region, or one with only armies
+        assert_eq!(map.ruler(Kabul, &players), None);
+        add_armies(&mut map, Kabul, Coalition::Britain, 2);
+        assert_eq!(map.ruler(Kabul, &players), None);
+
+        // Blue's armies count against red's lone tribe
+        add_tribes(&mut map, Kabul, Color::Red, 1);
+        assert_eq!(map.ruler(Kabul, &players), None);
+
+        // Ties don't rule
+        add_tribes(&mut map, Kabul, Color::Red, 1);
+        assert_eq!(map.ruler(Kabul, &players), None);
+
+        add_armies(&mut map, Kabul, Coalition::Russia, 1);
+        assert_eq!(map.ruler(Kabul, &players), Some(Color::Red));
+
+        // Russian armies count for black too
+        add_tribes(&mut map, Kabul, Color::Black, 2);
+        let rule = map.rule(Kabul, &players);
+        assert_eq!(rule.ruler, None);
+        assert_eq!(
+            rule.pieces,
+            [
+                RulingPieces {
+                    player: Color::Red,
+                    tribes: 2,
+                    armies: 1,
+                },
+                RulingPieces {
+                    player: Color::Blue,
+                    tribes: 0,
+                    armies: 2,
+                },
+                RulingPieces {
+                    player: Color::Black,
+                    tribes: 2,
+                    armies: 1,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_roads() {
+        let mut map = Map::new();
+        let border = map.border(Punjab, Kabul).unwrap();
+        assert_eq!(border, Border::new(Kabul, Punjab));
+
+        assert_eq!(
+            map.border(Kabul, Kabul),
+            Err(BorderError::SameRegion(Kabul))
+        );
+        assert_eq!(
+            map.border(Persia, Punjab),
+            Err(BorderError::NotAdjacent(Persia, Punjab))
+        );
+
+        let mut tray = BlockSet::new_tray();
+        map.add_roads(border, tray.take_up_to(2, Coalition::Russia))
+            .unwrap();
+        assert_eq!(map.roads_on(border).unwrap().count(Coalition::Russia), 2);
+
+        // Pairs that aren't on the map are rejected, and the blocks come back
+        let missing = Border::new(Persia, Punjab);
+        let (err, rejected) = map
+            .add_roads(missing, tray.take_up_to(1, Coalition::Russia))
+            .unwrap_err();
+        assert_eq!(err, BorderError::NotAdjacent(Persia, Punjab));
+        tray.add(rejected);
+        assert_eq!(
+            tray.count(Coalition::Russia) + map.total_block_counts()[Coalition::Russia],
+            12
+        );
+        assert!(map.roads_on(missing).is_err());
+        assert!(map.remove_roads(missing, 1, Coalition::Russia).is_err());
+
+        let removed = map.remove_roads(border, 3, Coalition::Russia).unwrap();
+        assert_eq!(removed.count(Coalition::Russia), 2);
+        assert_eq!(map.roads_on(border).unwrap().count(Coalition::Russia), 0);
+
+        assert_eq!(map.borders().count(), 9);
+        assert!(map
+            .borders()
+            .map(|(border, _)| border)
+            .tuple_windows()
+            .all(|(a, b)| a < b));
+    }
+
+    #[test]
+    fn test_deserialize_rejects_wrong_borders() {
+        let value = serde_json::to_value(Map::new()).unwrap();
+        assert!(serde_json::from_value::<Map>(value.clone()).is_ok());
+
+        // Herat and Punjab aren't neighbors
+        let mut extra = value.clone();
+        let mut border = extra["borders"][0].clone();
+        border[0] = serde_json::json!(["Herat", "Punjab"]);
+        extra["borders"].as_array_mut().unwrap().push(border);
+        assert!(serde_json::from_value::<Map>(extra).is_err());
+
+        let mut missing = value.clone();
+        missing["borders"].as_array_mut().unwrap().pop();
+        assert!(serde_json::from_value::<Map>(missing).is_err());
+
+        let mut duplicate = value;
+        let border = duplicate["borders"][0].clone();
+        duplicate["borders"].as_array_mut().unwrap().push(border);
+        assert!(serde_json::from_value::<Map>(duplicate).is_err());
+    }
+}
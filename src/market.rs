@@ -1,13 +1,16 @@
-use std::{mem, ops::Deref};
+use std::{iter, mem, ops::Deref};
 
 use itertools::{Itertools, Position};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
 use crate::{
-    cards::Card,
-    rupees::{IntoRupeeSet, RupeeSet},
+    cards::{event, Card, CardId},
+    deck::Deck,
+    rupees::{IntoRupeeSet, Rupee, RupeeSet},
 };
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum Column {
     Zero = 0,
     One = 1,
@@ -18,6 +21,19 @@ pub enum Column {
 }
 
 impl Column {
+    /// All the columns, from left to right
+    pub fn all() -> impl Iterator<Item = Column> + Clone {
+        [
+            Column::Zero,
+            Column::One,
+            Column::Two,
+            Column::Three,
+            Column::Four,
+            Column::Five,
+        ]
+        .into_iter()
+    }
+
     pub fn cost(&self) -> u8 {
         *self as u8
     }
@@ -29,7 +45,7 @@ impl Column {
 
 /// A card in the market, which has both the card itself as well as a pile of
 /// rupees
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct MarketCard {
     card: Card,
     rupees: RupeeSet,
@@ -52,12 +68,45 @@ impl MarketCard {
         (self.card, self.rupees)
     }
 
+    /// The number of rupees sitting on this card
+    pub fn rupees(&self) -> i8 {
+        self.rupees.count()
+    }
+
+    /// Has a rupee been placed on this card this turn
+    pub fn is_marked(&self) -> bool {
+        self.mark
+    }
+
+    /// Take up to `count` rupees off of this card
+    pub fn take_rupees(&mut self, count: i8) -> RupeeSet {
+        self.rupees.take_up_to(count)
+    }
+
     pub fn add_rupees(&mut self, rupees: impl IntoRupeeSet) {
         let rupees = rupees.into_set();
 
         if rupees.count() > 0 {
             self.mark = true;
-            self.rupees.add(rupees);
+            self.place_rupees(rupees);
+        }
+    }
+
+    /// Public Withdrawal can't be bought, and any rupees placed on it are
+    /// removed from the game
+    pub fn removes_rupees(&self) -> bool {
+        match self.card {
+            Card::Event(event::Card::Event(ref card)) => {
+                matches!(card.purchase, event::PurchaseEvent::PublicWithdrawal)
+            }
+            _ => false,
+        }
+    }
+
+    /// Put rupees on this card without marking it
+    fn place_rupees(&mut self, rupees: impl IntoRupeeSet) {
+        if !self.removes_rupees() {
+            self.rupees.add(rupees.into_set());
         }
     }
 }
@@ -70,7 +119,7 @@ impl Deref for MarketCard {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct MarketRow {
     cards: [Option<MarketCard>; 6],
 }
@@ -85,6 +134,10 @@ impl MarketRow {
         self.cards.get(column.index())?.as_ref()
     }
 
+    pub fn get_card_mut(&mut self, column: Column) -> Option<&mut MarketCard> {
+        self.cards.get_mut(column.index())?.as_mut()
+    }
+
     pub fn count(&self) -> usize {
         self.cards.iter().filter(|card| card.is_some()).count()
     }
@@ -97,7 +150,8 @@ impl MarketRow {
         let new_cards = cards.map(MarketCard::new);
         let cards = existing_cards.chain(new_cards);
 
-        cards.zip(&mut self.cards).for_each(|(card, slot)| {
+        // Zip the slots first, so that no card is drawn once the row is full
+        self.cards.iter_mut().zip(cards).for_each(|(slot, card)| {
             debug_assert!(slot.is_none());
             *slot = Some(card);
         });
@@ -116,12 +170,12 @@ impl MarketRow {
         {
             match slot {
                 Position::Last(card) | Position::Only(card) => {
-                    card.rupees.add(payment);
+                    card.place_rupees(payment);
                     return;
                 }
                 Position::First(card) | Position::Middle(card) => match payment.take_one() {
                     None => return,
-                    Some(rupee) => card.rupees.add(rupee),
+                    Some(rupee) => card.place_rupees(rupee),
                 },
             }
         }
@@ -131,9 +185,61 @@ impl MarketRow {
     pub fn take_card(&mut self, column: Column) -> Option<MarketCard> {
         self.cards[column.index()].take()
     }
+
+    /// Clean up this row at the end of a turn. See [`Market::cleanup`].
+    fn cleanup(&mut self, row: Row, deck: &mut Deck, events: &mut Vec<MarketEvent>) {
+        self.cards
+            .iter_mut()
+            .flatten()
+            .for_each(|card| card.mark = false);
+
+        // Event cards in the first column are discarded
+        if let Some(MarketCard {
+            card: Card::Event(_),
+            ..
+        }) = self.cards[0]
+        {
+            let (card, rupees) = self.cards[0].take().expect("slot is occupied").into_parts();
+            let rupees = rupees.count();
+
+            events.push(match card {
+                Card::Event(event::Card::Event(card)) => {
+                    MarketEvent::EventDiscarded { row, card, rupees }
+                }
+                Card::Event(event::Card::Dominance(card)) => {
+                    MarketEvent::DominanceCheck { row, card, rupees }
+                }
+                Card::Court(_) => unreachable!("slot holds an event card"),
+            });
+        }
+
+        // Shift everything left, keeping the rupees on each card
+        let existing_cards = mem::take(&mut self.cards).into_iter().flatten();
+        self.cards
+            .iter_mut()
+            .zip(existing_cards)
+            .for_each(|(slot, card)| *slot = Some(card));
+
+        // Deal new cards into the empty slots
+        for (column, slot) in Column::all().zip(self.cards.iter_mut()) {
+            if slot.is_none() {
+                let card = match deck.draw() {
+                    Some(card) => card,
+                    None => return,
+                };
+
+                events.push(MarketEvent::Dealt {
+                    row,
+                    column,
+                    card: card.id(),
+                });
+                *slot = Some(MarketCard::new(card));
+            }
+        }
+    }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Row {
     Top = 0,
     Bottom = 1,
@@ -148,13 +254,87 @@ impl Row {
     }
 }
 
-#[derive(Debug)]
+/// Something that happened while cleaning up the market at the end of a turn.
+/// Events are reported in the order they happened, so that the game can
+/// resolve them in that order.
+#[derive(Debug, PartialEq, Eq)]
+pub enum MarketEvent {
+    /// An event card was discarded from the first column. Its discard effect
+    /// should be resolved. Any rupees on it went back to the bank.
+    EventDiscarded {
+        row: Row,
+        card: event::EventCard,
+        rupees: i8,
+    },
+
+    /// A dominance check was discarded from the first column, and should be
+    /// resolved. Any rupees on it went back to the bank.
+    DominanceCheck { row: Row, card: CardId, rupees: i8 },
+
+    /// A card was dealt from the deck into an empty slot
+    Dealt {
+        row: Row,
+        column: Column,
+        card: CardId,
+    },
+}
+
+/// Where a single rupee paid for a purchase went
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Payment {
+    /// The rupee was placed on a card in the market
+    Card { row: Row, column: Column },
+
+    /// There was no card to place the rupee on, so it went back to the bank
+    Bank,
+
+    /// The rupee landed on Public Withdrawal, and was removed from the game
+    Removed { row: Row, column: Column },
+}
+
+/// An itemized record of a purchase from the market
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Receipt {
+    /// The card that was bought
+    pub card: CardId,
+
+    /// The price paid for the card
+    pub cost: i8,
+
+    /// Where each rupee of the cost went, from left to right
+    pub payments: Vec<Payment>,
+
+    /// The rupees that were sitting on the card, which go to the buyer
+    pub collected: i8,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum PurchaseError {
+    #[error("there's no card in the {row:?} row, column {column:?}")]
+    EmptySlot { row: Row, column: Column },
+
+    #[error("{} had a rupee placed on it this turn, so it can't be bought", .0.name())]
+    Marked(CardId),
+
+    #[error("costs {cost} rupees, but only {available} are available")]
+    CannotAfford { cost: i8, available: i8 },
+
+    #[error("{} can't be bought", .0.name())]
+    Unpurchasable(CardId),
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct Market {
     top: MarketRow,
     bottom: MarketRow,
 }
 
 impl Market {
+    /// Create a new, empty market
+    pub fn new() -> Self {
+        Self::default()
+    }
+
     pub fn get_row(&self, row: Row) -> &MarketRow {
         match row {
             Row::Top => &self.top,
@@ -162,8 +342,444 @@ impl Market {
         }
     }
 
+    pub fn get_row_mut(&mut self, row: Row) -> &mut MarketRow {
+        match row {
+            Row::Top => &mut self.top,
+            Row::Bottom => &mut self.bottom,
+        }
+    }
+
+    pub fn get_card(&self, row: Row, column: Column) -> Option<&MarketCard> {
+        self.get_row(row).get_card(column)
+    }
+
     pub fn fill_from(&mut self, cards: &mut impl Iterator<Item = Card>) {
         self.top.fill_from(cards);
         self.bottom.fill_from(cards);
     }
+
+    /// Check that the card in a slot can be bought with some funds, for the
+    /// given cost. The market doesn't price anything itself; costs come from
+    /// the game's pricing.
+    pub fn check_purchase(
+        &self,
+        row: Row,
+        column: Column,
+        cost: i8,
+        funds: &RupeeSet,
+    ) -> Result<(), PurchaseError> {
+        let card = self
+            .get_card(row, column)
+            .ok_or(PurchaseError::EmptySlot { row, column })?;
+
+        if card.removes_rupees() {
+            return Err(PurchaseError::Unpurchasable(card.id()));
+        }
+
+        if card.mark {
+            return Err(PurchaseError::Marked(card.id()));
+        }
+
+        match cost <= funds.count() {
+            true => Ok(()),
+            false => Err(PurchaseError::CannotAfford {
+                cost,
+                available: funds.count(),
+            }),
+        }
+    }
+
+    /// Buy the card in a slot for `cost` rupees, paying for it out of
+    /// `funds`. The cost is split evenly between the cards to its left in the
+    /// same row; if one of those slots is empty, its share goes on the card in
+    /// the other row instead, or back to the bank if that's empty too. Any
+    /// rupees sitting on the bought card are added to `funds`. On failure,
+    /// nothing is changed.
+    pub fn purchase(
+        &mut self,
+        row: Row,
+        column: Column,
+        cost: i8,
+        funds: &mut RupeeSet,
+    ) -> Result<(Card, Receipt), PurchaseError> {
+        let payments = self.pay_for(row, column, cost, funds)?;
+
+        let (card, rupees) = self
+            .get_row_mut(row)
+            .take_card(column)
+            .expect("slot was checked")
+            .into_parts();
+
+        let receipt = Receipt {
+            card: card.id(),
+            cost,
+            payments,
+            collected: rupees.count(),
+        };
+
+        funds.add(rupees);
+        Ok((card, receipt))
+    }
+
+    /// Check a purchase, then take its cost out of `funds` and split it
+    /// between the cards to the left. Any remainder goes on the leftmost
+    /// cards. Returns where each rupee went.
+    fn pay_for(
+        &mut self,
+        row: Row,
+        column: Column,
+        cost: i8,
+        funds: &mut RupeeSet,
+    ) -> Result<Vec<Payment>, PurchaseError> {
+        self.check_purchase(row, column, cost, funds)?;
+        let mut payment = funds.take_up_to(cost);
+
+        let lefts = column.cost() as i8;
+        let (share, remainder) = match lefts {
+            0 => (0, 0),
+            _ => (cost / lefts, cost % lefts),
+        };
+
+        let payments = Column::all()
+            .take_while(|&left| left < column)
+            .enumerate()
+            .flat_map(|(index, left)| {
+                let extra = (index as i8) < remainder;
+                iter::repeat_n(left, (share + extra as i8).max(0) as usize)
+            })
+            .map(|left| {
+                let rupee = payment.take_one().expect("cost was checked");
+                self.pay_onto(row, left, rupee)
+            })
+            .collect();
+
+        Ok(payments)
+    }
+
+    /// Place a rupee paid for a purchase on the card in a slot, or on the card
+    /// in the other row if the slot is empty
+    fn pay_onto(&mut self, row: Row, column: Column, rupee: Rupee) -> Payment {
+        for row in [row, row.other()] {
+            if let Some(card) = self.get_row_mut(row).get_card_mut(column) {
+                card.add_rupees(rupee);
+
+                return match card.removes_rupees() {
+                    true => Payment::Removed { row, column },
+                    false => Payment::Card { row, column },
+                };
+            }
+        }
+
+        Payment::Bank
+    }
+
+    /// Clean up the market at the end of a turn, following the rulebook:
+    /// clear every card's mark, discard any event cards (including dominance
+    /// checks) in the first column, shift the remaining cards left (along with
+    /// their rupees), and refill the empty slots from the deck, top row first.
+    pub fn cleanup(&mut self, deck: &mut Deck) -> Vec<MarketEvent> {
+        let mut events = Vec::new();
+
+        self.top.cleanup(Row::Top, deck, &mut events);
+        self.bottom.cleanup(Row::Bottom, deck, &mut events);
+
+        events
+    }
+
+    /// Pay the cost of an ability into the market. As with
+    /// [`MarketRow::spend_for_ability`], rupees are placed one at a time from
+    /// back to front, going down each column before moving to the next. Any
+    /// excess rupees are piled onto the last card reached. If there are *no*
+    /// cards, the rupees are simply lost.
+    pub fn spend_for_ability(&mut self, mut payment: RupeeSet) {
+        let cards = self
+            .top
+            .cards
+            .iter_mut()
+            .zip(self.bottom.cards.iter_mut())
+            .rev()
+            .flat_map(|(top, bottom)| [top, bottom])
+            .filter_map(|slot| slot.as_mut());
+
+        for slot in cards.with_position() {
+            match slot {
+                Position::Last(card) | Position::Only(card) => {
+                    card.place_rupees(payment);
+                    return;
+                }
+                Position::First(card) | Position::Middle(card) => match payment.take_one() {
+                    None => return,
+                    Some(rupee) => card.place_rupees(rupee),
+                },
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A full market of court cards 1 through 12
+    fn full_market() -> Market {
+        let mut market = Market::new();
+        let mut cards = (1..=12).map(|number| Card::from_id(CardId::new(number).unwrap()));
+
+        market.fill_from(&mut cards);
+        market
+    }
+
+    #[test]
+    fn test_purchase() {
+        let mut market = full_market();
+        let mut funds = RupeeSet::new(5);
+
+        market
+            .get_row_mut(Row::Bottom)
+            .get_card_mut(Column::Three)
+            .unwrap()
+            .rupees
+            .add(RupeeSet::new(2));
+
+        let (card, receipt) = market
+            .purchase(Row::Bottom, Column::Three, 3, &mut funds)
+            .unwrap();
+
+        assert_eq!(card.id(), CardId::new(10).unwrap());
+        assert_eq!(receipt.cost, 3);
+        assert_eq!(receipt.collected, 2);
+        assert_eq!(
+            receipt.payments,
+            [Column::Zero, Column::One, Column::Two].map(|column| Payment::Card {
+                row: Row::Bottom,
+                column
+            })
+        );
+
+        assert_eq!(funds.count(), 4);
+        assert!(market.get_card(Row::Bottom, Column::Three).is_none());
+
+        for column in [Column::Zero, Column::One, Column::Two] {
+            let card = market.get_card(Row::Bottom, column).unwrap();
+            assert_eq!(card.rupees(), 1);
+            assert!(card.is_marked());
+        }
+
+        assert!(!market.get_card(Row::Top, Column::Zero).unwrap().is_marked());
+    }
+
+    #[test]
+    fn test_purchase_past_empty_slots() {
+        let mut market = full_market();
+        let mut funds = RupeeSet::new(4);
+
+        market.get_row_mut(Row::Top).take_card(Column::Zero);
+        market.get_row_mut(Row::Top).take_card(Column::One);
+        market.get_row_mut(Row::Bottom).take_card(Column::One);
+
+        let (_, receipt) = market
+            .purchase(Row::Top, Column::Two, 2, &mut funds)
+            .unwrap();
+
+        assert_eq!(
+            receipt.payments,
+            [
+                Payment::Card {
+                    row: Row::Bottom,
+                    column: Column::Zero
+                },
+                Payment::Bank,
+            ]
+        );
+        assert_eq!(funds.count(), 2);
+        assert_eq!(
+            market.get_card(Row::Bottom, Column::Zero).unwrap().rupees(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_purchase_errors() {
+        let mut market = full_market();
+        let mut funds = RupeeSet::new(2);
+
+        assert_eq!(
+            market
+                .purchase(Row::Top, Column::Three, 3, &mut funds)
+                .unwrap_err(),
+            PurchaseError::CannotAfford {
+                cost: 3,
+                available: 2
+            }
+        );
+        assert_eq!(funds.count(), 2);
+        assert_eq!(market.get_card(Row::Top, Column::Zero).unwrap().rupees(), 0);
+
+        market
+            .purchase(Row::Top, Column::One, 1, &mut funds)
+            .unwrap();
+
+        assert_eq!(
+            market
+                .purchase(Row::Top, Column::One, 1, &mut funds)
+                .unwrap_err(),
+            PurchaseError::EmptySlot {
+                row: Row::Top,
+                column: Column::One
+            }
+        );
+        assert_eq!(
+            market
+                .purchase(Row::Top, Column::Zero, 0, &mut funds)
+                .unwrap_err(),
+            PurchaseError::Marked(CardId::new(1).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_purchase_at_double_rate() {
+        let mut market = full_market();
+        let mut funds = RupeeSet::new(5);
+
+        let (_, receipt) = market
+            .purchase(Row::Top, Column::Two, 4, &mut funds)
+            .unwrap();
+
+        assert_eq!(receipt.cost, 4);
+        assert_eq!(receipt.payments.len(), 4);
+        assert_eq!(funds.count(), 1);
+
+        for column in [Column::Zero, Column::One] {
+            assert_eq!(market.get_card(Row::Top, column).unwrap().rupees(), 2);
+        }
+    }
+
+    fn ids(cards: impl IntoIterator<Item = u8>) -> Vec<CardId> {
+        cards
+            .into_iter()
+            .map(|number| CardId::new(number).unwrap())
+            .collect()
+    }
+
+    fn row_ids(market: &Market, row: Row) -> Vec<Option<CardId>> {
+        market
+            .get_row(row)
+            .view()
+            .iter()
+            .map(|slot| slot.as_ref().map(|card| card.id()))
+            .collect()
+    }
+
+    #[test]
+    fn test_cleanup_shifts_and_refills() {
+        let mut market = full_market();
+        let mut deck = Deck::from_ids(ids(20..30)).unwrap();
+        let mut funds = RupeeSet::new(4);
+
+        // Buy the third card in the top row, marking the two before it
+        market
+            .purchase(Row::Top, Column::Two, 2, &mut funds)
+            .unwrap();
+        market.get_row_mut(Row::Bottom).take_card(Column::Five);
+
+        let events = market.cleanup(&mut deck);
+
+        assert_eq!(
+            events,
+            [
+                MarketEvent::Dealt {
+                    row: Row::Top,
+                    column: Column::Five,
+                    card: CardId::new(20).unwrap()
+                },
+                MarketEvent::Dealt {
+                    row: Row::Bottom,
+                    column: Column::Five,
+                    card: CardId::new(21).unwrap()
+                },
+            ]
+        );
+
+        assert_eq!(
+            row_ids(&market, Row::Top),
+            ids([1, 2, 4, 5, 6, 20])
+                .into_iter()
+                .map(Some)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(deck.len(), 8);
+
+        // Marks are cleared, but rupees stay on their cards
+        let first = market.get_card(Row::Top, Column::Zero).unwrap();
+        assert_eq!(first.rupees(), 1);
+        assert!(!first.is_marked());
+    }
+
+    #[test]
+    fn test_cleanup_discards_events() {
+        let rumor = CardId::from_name("Rumor").unwrap();
+        let dominance = CardId::from_name("Dominance Check").unwrap();
+
+        let mut market = Market::new();
+        let mut cards = [rumor]
+            .into_iter()
+            .chain(ids(1..=5))
+            .chain([dominance])
+            .chain(ids(6..=10))
+            .map(Card::from_id);
+        market.fill_from(&mut cards);
+
+        market
+            .get_row_mut(Row::Top)
+            .get_card_mut(Column::Zero)
+            .unwrap()
+            .rupees
+            .add(RupeeSet::new(3));
+
+        let mut deck = Deck::from_ids(ids([50])).unwrap();
+        let events = market.cleanup(&mut deck);
+
+        assert!(matches!(
+            &events[..],
+            [
+                MarketEvent::EventDiscarded {
+                    row: Row::Top,
+                    card,
+                    rupees: 3,
+                },
+                MarketEvent::Dealt {
+                    row: Row::Top,
+                    column: Column::Five,
+                    ..
+                },
+                MarketEvent::DominanceCheck {
+                    row: Row::Bottom,
+                    rupees: 0,
+                    ..
+                },
+            ] if card.id() == rumor
+        ));
+
+        // The deck ran out, so the bottom row is left with a gap
+        assert_eq!(
+            row_ids(&market, Row::Bottom),
+            ids(6..=10)
+                .into_iter()
+                .map(Some)
+                .chain([None])
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_cleanup_keeps_court_cards() {
+        let mut market = full_market();
+        let mut deck = Deck::default();
+
+        assert!(market.cleanup(&mut deck).is_empty());
+        assert_eq!(
+            row_ids(&market, Row::Top),
+            ids(1..=6).into_iter().map(Some).collect::<Vec<_>>()
+        );
+    }
 }
@@ -10,7 +10,7 @@ use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 use crate::{
-    cards::court::{self},
+    cards::{court, CardId},
     cylinders::{CylinderSet, SingleCylinderSet},
     game,
     primitives::{Coalition, Suit},
@@ -18,7 +18,7 @@ use crate::{
 };
 
 /// A player color
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize, Enum)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, Enum)]
 pub enum Color {
     Red,
     Blue,
@@ -28,7 +28,7 @@ pub enum Color {
 }
 
 /// A card in a player's court
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct CourtCard {
     /// Details about the card itself
     card: court::Card,
@@ -49,7 +49,7 @@ impl Deref for CourtCard {
 }
 
 /// Which of the tableau to play a card
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Side {
     Left,
     Right,
@@ -63,14 +63,39 @@ impl CourtCard {
             tapped: false,
         }
     }
+
+    pub fn into_parts(self) -> (court::Card, CylinderSet) {
+        (self.card, self.spies)
+    }
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct Court {
     pub cards: VecDeque<CourtCard>,
 }
 
 impl Court {
+    /// Find the position of a card in this court
+    pub fn position(&self, id: CardId) -> Option<usize> {
+        self.cards.iter().position(|card| card.id() == id)
+    }
+
+    pub fn get(&self, id: CardId) -> Option<&CourtCard> {
+        self.cards.iter().find(|card| card.id() == id)
+    }
+
+    pub fn get_mut(&mut self, id: CardId) -> Option<&mut CourtCard> {
+        self.cards.iter_mut().find(|card| card.id() == id)
+    }
+
+    /// Add a card to one side of the court
+    pub fn play(&mut self, card: CourtCard, side: Side) {
+        match side {
+            Side::Left => self.cards.push_front(card),
+            Side::Right => self.cards.push_back(card),
+        }
+    }
+
     pub fn star_count(&self, suit: Suit) -> i8 {
         self.cards
             .iter()
@@ -80,14 +105,21 @@ impl Court {
     }
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct Hand {
     pub cards: Vec<court::Card>,
 }
 
+impl Hand {
+    /// Find the position of a card in this hand
+    pub fn position(&self, id: CardId) -> Option<usize> {
+        self.cards.iter().position(|card| card.id() == id)
+    }
+}
+
 /// Permanent modifiers that can affect a player, triggered by event cards.
 /// All of these are reset after a Dominance Check.
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct Effects {
     /// May ignore bribes
     pub courtly_manners: bool,
@@ -108,6 +140,18 @@ pub struct Effects {
     pub new_tactics: bool,
 }
 
+/// A single one of a player's [`Effects`], so that event cards can name the
+/// effect they have and the player it applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Effect {
+    CourtlyManners,
+    KohINoor,
+    Rumor,
+    Nationalism,
+    NationBuilding,
+    NewTactics,
+}
+
 impl Effects {
     pub fn new() -> Self {
         Self::default()
@@ -116,10 +160,26 @@ impl Effects {
     pub fn clear(&mut self) {
         *self = Self::new()
     }
+
+    fn flag_mut(&mut self, effect: Effect) -> &mut bool {
+        match effect {
+            Effect::CourtlyManners => &mut self.courtly_manners,
+            Effect::KohINoor => &mut self.kohinoor,
+            Effect::Rumor => &mut self.rumor,
+            Effect::Nationalism => &mut self.nationalism,
+            Effect::NationBuilding => &mut self.nation_building,
+            Effect::NewTactics => &mut self.new_tactics,
+        }
+    }
+
+    /// Turn on an effect. It lasts until the next dominance check.
+    pub fn apply(&mut self, effect: Effect) {
+        *self.flag_mut(effect) = true
+    }
 }
 
 /// All of the state for a single player
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct PlayerState {
     /// The player's court cards, in order
     pub court: Court,
@@ -183,16 +243,35 @@ impl PlayerState {
         self.star_count(Suit::Intelligence) + 2
     }
 
+    /// The number of court cards over the court size limit, which must be
+    /// discarded at the end of the turn
+    pub fn court_overflow(&self) -> usize {
+        self.court
+            .cards
+            .len()
+            .saturating_sub(self.court_size().max(0) as usize)
+    }
+
+    /// The number of cards over the hand size limit, which must be discarded
+    /// at the end of the turn
+    pub fn hand_overflow(&self) -> usize {
+        self.hand
+            .cards
+            .len()
+            .saturating_sub(self.hand_size().max(0) as usize)
+    }
+
     /// Add some cylinders back to the bank
     pub fn discard_cylinders(&mut self, cylinders: SingleCylinderSet) {
         self.bank.add(cylinders)
     }
 
-    /// Find how much influence the player has with their current faction
+    /// Find how much influence the player has with their current faction.
+    /// Only patriots in the court count; cards in hand haven't been played.
     pub fn influence(&self, game_effects: &game::Effects) -> i8 {
         let patriots: i8 = match self.effects.rumor {
             false => self
-                .hand
+                .court
                 .cards
                 .iter()
                 .filter(|card| card.patriot.is_some())
@@ -213,7 +292,7 @@ impl PlayerState {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Player {
     pub state: PlayerState,
     pub color: Color,
@@ -230,7 +309,7 @@ impl From<PlayerInit> for Player {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct PlayerInit {
     pub color: Color,
     pub loyalty: Coalition,
@@ -238,14 +317,14 @@ pub struct PlayerInit {
 }
 
 /// The set of players currently playing a game, in turn order
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct PlayerSet {
     players: Vec<Player>,
 }
 
-#[derive(Debug, Copy, Clone, Error)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Error)]
 #[error("duplicate player color: {0:?}")]
-pub struct DuplicateColor(Color);
+pub struct DuplicateColor(pub Color);
 
 impl PlayerSet {
     pub fn new(players: impl IntoIterator<Item = PlayerInit>) -> Result<Self, DuplicateColor> {
@@ -313,3 +392,29 @@ impl IndexMut<Color> for PlayerSet {
             .expect("no player matching color")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::cards::CardId;
+
+    fn court_card(name: &str) -> court::Card {
+        court::Card::from_id(CardId::from_name(name).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_influence_counts_court_patriots() {
+        let effects = game::Effects::new();
+        let mut player = PlayerState::new(Coalition::Britain);
+        assert_eq!(player.influence(&effects), 1);
+
+        // A patriot in hand hasn't been played yet
+        player.hand.cards.push(court_card("Mohan Lal"));
+        assert_eq!(player.influence(&effects), 1);
+
+        let card = player.hand.cards.remove(0);
+        player.court.cards.push_back(CourtCard::new(card));
+        assert_eq!(player.influence(&effects), 2);
+    }
+}
@@ -1,11 +1,14 @@
 use std::mem;
 
+use serde::{Deserialize, Serialize};
+
 /// A single rupee, can be taken from or added to sets
 #[derive(Debug, Clone, Copy, Default)]
 pub struct Rupee;
 
 /// A set of rupees
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(transparent)]
 pub struct RupeeSet {
     count: i8,
 }
@@ -0,0 +1,234 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::game::Game;
+
+/// The save format version written by this version of the engine. Whenever a
+/// change to the game state alters its serialized form in a way that older
+/// saves can't be read as-is, bump this and add a migration to [`MIGRATIONS`].
+pub const FORMAT_VERSION: u32 = 1;
+
+/// The version of this crate, recorded in every save for diagnostics
+pub const ENGINE_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// A migration upgrades a save's JSON in place, from one format version to
+/// the next. It only has to touch the parts of the save that changed, but the
+/// game must still be playable afterwards: anything the new version adds has
+/// to be rebuilt from the rest of the save, not left empty.
+pub type Migration = fn(&mut Value) -> Result<(), String>;
+
+/// The migrations for every format version, in order. `MIGRATIONS[n]` upgrades
+/// a save from version `n + 1` to version `n + 2`.
+static MIGRATIONS: [Migration; FORMAT_VERSION as usize - 1] = [];
+
+/// The set of rules a game is being played with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Ruleset {
+    /// The base game of Pax Pamir: Second Edition
+    Base,
+}
+
+/// A saved game, along with everything needed to load it in a later version
+/// of the engine
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SaveGame {
+    /// The save format version this save was written with
+    pub format_version: u32,
+
+    /// The version of the engine that wrote this save
+    pub engine_version: String,
+
+    /// The seed the game was set up with
+    pub seed: u64,
+
+    /// The rules the game is being played with
+    pub ruleset: Ruleset,
+
+    /// The game itself
+    pub game: Game,
+}
+
+#[derive(Debug, Error)]
+pub enum LoadError {
+    #[error("save data is malformed")]
+    Malformed(#[from] serde_json::Error),
+
+    #[error("save data has no format version")]
+    MissingVersion,
+
+    #[error(
+        "save format version {found} is newer than the newest supported version ({supported})"
+    )]
+    TooNew { found: u32, supported: u32 },
+
+    #[error("failed to migrate save from format version {from}: {reason}")]
+    Migration { from: u32, reason: String },
+}
+
+/// Bring a save up to date by running every migration after its current
+/// format version. Returns the final format version.
+fn migrate(save: &mut Value, migrations: &[Migration]) -> Result<u32, LoadError> {
+    let latest = migrations.len() as u32 + 1;
+
+    let version = save
+        .get("format_version")
+        .and_then(Value::as_u64)
+        .and_then(|version| u32::try_from(version).ok())
+        .filter(|&version| version > 0)
+        .ok_or(LoadError::MissingVersion)?;
+
+    if version > latest {
+        return Err(LoadError::TooNew {
+            found: version,
+            supported: latest,
+        });
+    }
+
+    for from in version..latest {
+        migrations[from as usize - 1](save)
+            .map_err(|reason| LoadError::Migration { from, reason })?;
+        save["format_version"] = (from + 1).into();
+    }
+
+    Ok(latest)
+}
+
+impl SaveGame {
+    /// Wrap a game in a save with the current format and engine versions
+    pub fn new(game: Game, seed: u64, ruleset: Ruleset) -> Self {
+        Self {
+            format_version: FORMAT_VERSION,
+            engine_version: ENGINE_VERSION.to_owned(),
+            seed,
+            ruleset,
+            game,
+        }
+    }
+
+    pub fn to_value(&self) -> serde_json::Result<Value> {
+        serde_json::to_value(self)
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string(self)
+    }
+
+    /// Load a save written by this or any earlier version of the engine,
+    /// migrating it to the current format
+    pub fn from_value(mut save: Value) -> Result<Self, LoadError> {
+        migrate(&mut save, &MIGRATIONS)?;
+        serde_json::from_value(save).map_err(LoadError::Malformed)
+    }
+
+    /// Load a save from JSON, migrating it to the current format
+    pub fn from_json(json: &str) -> Result<Self, LoadError> {
+        Self::from_value(serde_json::from_str(json)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::json;
+
+    use crate::{
+        player::{Color, PlayerInit},
+        primitives::Coalition,
+    };
+
+    fn new_save() -> SaveGame {
+        let players = [Color::Red, Color::Blue].map(|color| PlayerInit {
+            color,
+            loyalty: Coalition::Afghanistan,
+            name: format!("{:?}", color),
+        });
+
+        SaveGame::new(Game::new(players, 12).unwrap(), 12, Ruleset::Base)
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let save = new_save();
+        let json = save.to_json().unwrap();
+        let loaded = SaveGame::from_json(&json).unwrap();
+
+        assert_eq!(loaded.format_version, FORMAT_VERSION);
+        assert_eq!(loaded.engine_version, ENGINE_VERSION);
+        assert_eq!(loaded.seed, 12);
+        assert_eq!(loaded.ruleset, Ruleset::Base);
+        assert_eq!(loaded.game.deck.len(), save.game.deck.len());
+        assert_eq!(loaded.to_json().unwrap(), json);
+    }
+
+    #[test]
+    fn test_migrations_run_in_order() {
+        let migrations: [Migration; 2] = [
+            |save| {
+                save["steps"] = json!(["first"]);
+                Ok(())
+            },
+            |save| {
+                save["steps"]
+                    .as_array_mut()
+                    .ok_or("missing steps")?
+                    .push("second".into());
+                Ok(())
+            },
+        ];
+
+        let mut save = json!({ "format_version": 1 });
+        assert_eq!(migrate(&mut save, &migrations).unwrap(), 3);
+        assert_eq!(
+            save,
+            json!({ "format_version": 3, "steps": ["first", "second"] })
+        );
+
+        let mut save = json!({ "format_version": 2, "steps": [] });
+        assert_eq!(migrate(&mut save, &migrations).unwrap(), 3);
+        assert_eq!(save, json!({ "format_version": 3, "steps": ["second"] }));
+    }
+
+    #[test]
+    fn test_failed_migration() {
+        let migrations: [Migration; 1] = [|_| Err("unrecognized board".to_owned())];
+        let mut save = json!({ "format_version": 1 });
+
+        assert!(matches!(
+            migrate(&mut save, &migrations),
+            Err(LoadError::Migration { from: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn test_current_version_is_untouched() {
+        let mut save = json!({ "format_version": FORMAT_VERSION, "seed": 10 });
+        let original = save.clone();
+
+        assert_eq!(migrate(&mut save, &MIGRATIONS).unwrap(), FORMAT_VERSION);
+        assert_eq!(save, original);
+    }
+
+    #[test]
+    fn test_too_new() {
+        let mut save = json!({ "format_version": FORMAT_VERSION + 1 });
+
+        assert!(matches!(
+            migrate(&mut save, &MIGRATIONS),
+            Err(LoadError::TooNew { .. })
+        ));
+    }
+
+    #[test]
+    fn test_missing_version() {
+        assert!(matches!(
+            SaveGame::from_json(r#"{"seed": 10}"#),
+            Err(LoadError::MissingVersion)
+        ));
+        assert!(matches!(
+            SaveGame::from_json(r#"{"format_version": 0}"#),
+            Err(LoadError::MissingVersion)
+        ));
+    }
+}
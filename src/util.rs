@@ -1,4 +1,4 @@
-use std::{cmp::Ordering, iter, mem, ops::AddAssign};
+use std::{cmp::Ordering, fmt, iter, mem, ops::AddAssign};
 
 use enum_map::{enum_map, Enum, EnumMap};
 
@@ -18,10 +18,6 @@ impl<K: Enum<bool>> EnumSet<K> {
         self.set.values().filter(|&&b| b).count()
     }
 
-    pub fn is_empty(&self) -> bool {
-        self.set.values().all(|&b| !b)
-    }
-
     pub fn set(&mut self, key: K, setting: bool) -> bool {
         mem::replace(&mut self.set[key], setting)
     }
@@ -31,30 +27,15 @@ impl<K: Enum<bool>> EnumSet<K> {
         self.set(key, true)
     }
 
-    /// Remove a key from the set, and return true if it was already present
-    pub fn remove(&mut self, key: K) -> bool {
-        self.set(key, false)
-    }
-
-    /// Check if a key is in this set
-    pub fn contains(&self, key: K) -> bool {
-        self.set[key]
-    }
-
     /// Iterate over all the keys in this set
     pub fn iter(&self) -> impl Iterator<Item = K> + '_ {
-        self.set.iter().filter_map(|(k, &b)| b.then(|| k))
+        self.set.iter().filter_map(|(k, &b)| b.then_some(k))
     }
 
     /// Iterate over all the keys in this set.
     // TODO: impl IntoIterator
     pub fn into_iter(self) -> impl Iterator<Item = K> {
-        self.set.into_iter().filter_map(|(k, b)| b.then(|| k))
-    }
-
-    /// Remove all the keys in this set
-    pub fn clear(&mut self) {
-        *self = Self::new();
+        self.set.into_iter().filter_map(|(k, b)| b.then_some(k))
     }
 }
 
@@ -90,6 +71,12 @@ impl<K: Enum<bool>> Extend<K> for EnumSet<K> {
     }
 }
 
+impl<K: Enum<bool> + fmt::Debug> fmt::Debug for EnumSet<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
 impl<K: Enum<bool>> PartialEq for EnumSet<K> {
     fn eq(&self, other: &Self) -> bool {
         self.set.as_slice() == other.set.as_slice()
@@ -138,5 +125,5 @@ where
         }
     });
 
-    unique.then(|| best)
+    unique.then_some(best)
 }